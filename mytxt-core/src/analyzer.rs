@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, PoisonError};
+use tantivy::tokenizer::{
+    LowerCaser, StopWordFilter, TextAnalyzer, Token, TokenFilter, TokenStream, Tokenizer,
+};
+use tantivy::Index;
+use tantivy_jieba::jieba_rs::Jieba;
+use tantivy_jieba::{CustomJiebaTokenizer, JiebaTokenStream};
+use zhconv::{get_builtin_converter, Variant, ZhConverter};
+
+pub const JIEBA: &str = "jieba";
+pub const JIEBA_NORMALIZED: &str = "jieba_normalized";
+
+// 索引使用的分词器，随索引的 schema 一起保存
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AnalyzerKind {
+    // 仅 jieba 分词
+    Jieba,
+    // jieba 分词 + 繁简转换 + 全半角折叠 + 小写
+    JiebaNormalized,
+}
+
+impl AnalyzerKind {
+    // 写入 schema 的分词器名称，查询时 QueryParser 会按字段取回同一个分词器
+    pub fn tokenizer_name(&self) -> &'static str {
+        match self {
+            AnalyzerKind::Jieba => JIEBA,
+            AnalyzerKind::JiebaNormalized => JIEBA_NORMALIZED,
+        }
+    }
+}
+
+// Everything that shapes the token stream, handed to the indexer as a snapshot of the settings.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct AnalyzerOptions {
+    pub kind: AnalyzerKind,
+    // jieba 用户词典，每行 "词语 [词频] [词性]"
+    pub user_dict: String,
+    // 停用词，每行一个
+    pub stopwords: String,
+}
+
+// Register every analyzer an index may have been built with.
+// The schema decides which one a field actually uses.
+pub fn register_tokenizers(index: &Index, options: &AnalyzerOptions) {
+    let jieba = SharedJiebaTokenizer::new(build_jieba(&options.user_dict));
+    let stopwords: Vec<&str> = options
+        .stopwords
+        .lines()
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let tokenizers = index.tokenizers();
+    tokenizers.register(
+        JIEBA,
+        TextAnalyzer::builder(jieba.clone())
+            .filter(StopWordFilter::remove(
+                stopwords.iter().map(|w| w.to_string()),
+            ))
+            .build(),
+    );
+    tokenizers.register(
+        JIEBA_NORMALIZED,
+        TextAnalyzer::builder(WidthFolding::new(jieba))
+            .filter(SimplifiedChinese)
+            .filter(LowerCaser)
+            .filter(StopWordFilter::remove(
+                stopwords.iter().map(|w| normalize_term(w)),
+            ))
+            .build(),
+    );
+}
+
+fn build_jieba(user_dict: &str) -> Jieba {
+    let mut jieba = Jieba::new();
+    for line in user_dict.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(word) = parts.next() else {
+            continue;
+        };
+        // 词频缺省或无法解析时交给 jieba 自动估算，保证该词能被切出来
+        let freq = parts.next().and_then(|f| f.parse::<usize>().ok());
+        let tag = parts.next();
+        jieba.add_word(word, freq, tag);
+    }
+    jieba
+}
+
+// Apply the normalizing filters to a single term, for word lists that bypass the tokenizer.
+pub fn normalize_term(term: &str) -> String {
+    let folded: String = term.chars().map(fold_width).collect();
+    get_builtin_converter(Variant::ZhCN)
+        .convert(&folded)
+        .to_lowercase()
+}
+
+/// Jieba tokenizer over a shared dictionary, delegating to `tantivy_jieba::CustomJiebaTokenizer`.
+/// Tantivy clones analyzers freely and cloning a `Jieba` copies the whole dictionary,
+/// so clones check a tokenizer out of a shared pool on first use and return it when dropped.
+/// A new one is only built when more analyzers tokenize at the same time than the pool holds.
+pub struct SharedJiebaTokenizer {
+    template: Arc<CustomJiebaTokenizer>,
+    pool: Arc<Mutex<Vec<CustomJiebaTokenizer>>>,
+    tokenizer: Option<CustomJiebaTokenizer>,
+}
+
+impl SharedJiebaTokenizer {
+    pub fn new(jieba: Jieba) -> Self {
+        Self {
+            template: Arc::new(CustomJiebaTokenizer::new(jieba)),
+            pool: Arc::default(),
+            tokenizer: None,
+        }
+    }
+}
+
+impl Clone for SharedJiebaTokenizer {
+    fn clone(&self) -> Self {
+        Self {
+            template: self.template.clone(),
+            pool: self.pool.clone(),
+            tokenizer: None,
+        }
+    }
+}
+
+impl Drop for SharedJiebaTokenizer {
+    fn drop(&mut self) {
+        if let Some(tokenizer) = self.tokenizer.take() {
+            self.pool
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(tokenizer);
+        }
+    }
+}
+
+impl Tokenizer for SharedJiebaTokenizer {
+    type TokenStream<'a> = JiebaTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        let Self {
+            template,
+            pool,
+            tokenizer,
+        } = self;
+        tokenizer
+            .get_or_insert_with(|| {
+                let pooled = pool.lock().unwrap_or_else(PoisonError::into_inner).pop();
+                pooled.unwrap_or_else(|| CustomJiebaTokenizer::clone(template))
+            })
+            .token_stream(text)
+    }
+}
+
+// 全角转半角：全角 ASCII 区段 (U+FF01..U+FF5E) 与全角空格 (U+3000)
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// Folds full-width characters to half-width before the wrapped tokenizer sees the text,
+/// so "ＡＢＣ１２３" is segmented exactly like "ABC123".
+/// Token offsets are mapped back onto the original text.
+#[derive(Clone)]
+pub struct WidthFolding<T> {
+    tokenizer: T,
+    folded: String,
+    // 折叠后文本的每个字节对应的原文字节位置，末尾多存一个原文长度
+    offsets: Vec<usize>,
+}
+
+impl<T> WidthFolding<T> {
+    pub fn new(tokenizer: T) -> Self {
+        Self {
+            tokenizer,
+            folded: String::new(),
+            offsets: Vec::new(),
+        }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for WidthFolding<T> {
+    type TokenStream<'a> = WidthFoldingTokenStream<'a, T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        let Self {
+            tokenizer,
+            folded,
+            offsets,
+        } = self;
+        folded.clear();
+        offsets.clear();
+        for (i, c) in text.char_indices() {
+            let c = fold_width(c);
+            offsets.extend(std::iter::repeat_n(i, c.len_utf8()));
+            folded.push(c);
+        }
+        offsets.push(text.len());
+        WidthFoldingTokenStream {
+            tail: tokenizer.token_stream(folded.as_str()),
+            offsets,
+        }
+    }
+}
+
+pub struct WidthFoldingTokenStream<'a, T> {
+    tail: T,
+    offsets: &'a [usize],
+}
+
+impl<T: TokenStream> TokenStream for WidthFoldingTokenStream<'_, T> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+        let token = self.tail.token_mut();
+        token.offset_from = self.offsets[token.offset_from];
+        token.offset_to = self.offsets[token.offset_to];
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+/// Token filter that converts Traditional Chinese terms to Simplified Chinese
+/// using mainland phrasing, e.g. "軟體" becomes "软件".
+#[derive(Clone)]
+pub struct SimplifiedChinese;
+
+impl TokenFilter for SimplifiedChinese {
+    type Tokenizer<T: Tokenizer> = SimplifiedChineseFilter<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> Self::Tokenizer<T> {
+        SimplifiedChineseFilter { tokenizer }
+    }
+}
+
+#[derive(Clone)]
+pub struct SimplifiedChineseFilter<T> {
+    tokenizer: T,
+}
+
+impl<T: Tokenizer> Tokenizer for SimplifiedChineseFilter<T> {
+    type TokenStream<'a> = SimplifiedChineseTokenStream<T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        SimplifiedChineseTokenStream {
+            tail: self.tokenizer.token_stream(text),
+            converter: get_builtin_converter(Variant::ZhCN),
+        }
+    }
+}
+
+pub struct SimplifiedChineseTokenStream<T> {
+    tail: T,
+    converter: &'static ZhConverter,
+}
+
+impl<T: TokenStream> TokenStream for SimplifiedChineseTokenStream<T> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+        // Plain ASCII has nothing to convert.
+        if !self.tail.token().text.is_ascii() {
+            let converted = self.converter.convert(&self.tail.token().text);
+            self.tail.token_mut().text = converted;
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_term_folds_width_script_and_case() {
+        assert_eq!(normalize_term("ＡＢＣ１２３"), "abc123");
+        assert_eq!(normalize_term("Ｈｅｌｌｏ　World"), "hello world");
+        assert_eq!(normalize_term("測試"), "测试");
+        assert_eq!(normalize_term("軟體"), "软件");
+        assert_eq!(normalize_term("已是简体"), "已是简体");
+    }
+}
@@ -0,0 +1,421 @@
+use std::fmt;
+use std::ops::Bound;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tantivy::query::{
+    BitSetDocSet, BooleanQuery, BoostQuery, ConstScorer, EmptyQuery, EnableScoring, Explanation,
+    Occur, Query, QueryParser, QueryParserError, Scorer, Weight,
+};
+use tantivy::query_grammar::{UserInputAst, UserInputBound, UserInputLeaf};
+use tantivy::schema::{Field, FieldType, IndexRecordOption};
+use tantivy::{DocId, DocSet, Index, Score, SegmentReader, TantivyError, Term, TERMINATED};
+use tantivy_common::BitSet;
+
+// 包装任意查询，使其在收集结果的过程中响应取消信号。
+// Every scorer polls the token while it walks its postings and simply runs out
+// of documents once the token is set, so any collector stops within a few docs.
+// Segments not started yet fail right away instead of building their scorer.
+pub struct CancellableQuery {
+    inner: Box<dyn Query>,
+    token: Arc<AtomicBool>,
+}
+
+impl CancellableQuery {
+    pub fn new(inner: Box<dyn Query>, token: Arc<AtomicBool>) -> Self {
+        Self { inner, token }
+    }
+}
+
+impl Clone for CancellableQuery {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.box_clone(),
+            token: self.token.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for CancellableQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CancellableQuery")
+            .field(&self.inner)
+            .finish()
+    }
+}
+
+impl Query for CancellableQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(CancellableWeight {
+            inner: self.inner.weight(enable_scoring)?,
+            token: self.token.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.inner.query_terms(visitor);
+    }
+}
+
+fn cancelled_error() -> TantivyError {
+    TantivyError::InternalError("search cancelled".to_string())
+}
+
+struct CancellableWeight {
+    inner: Box<dyn Weight>,
+    token: Arc<AtomicBool>,
+}
+
+impl CancellableWeight {
+    fn check(&self) -> tantivy::Result<()> {
+        if self.token.load(Ordering::Relaxed) {
+            return Err(cancelled_error());
+        }
+        Ok(())
+    }
+}
+
+// 批量遍历交给内层查询自己的实现（词项查询的分块遍历、block-WAND 剪枝等），
+// 取消后不再把文档交给 collector；建 scorer 前遍历词典的查询由 CancellableRangeQuery 自行检查。
+impl Weight for CancellableWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        self.check()?;
+        Ok(Box::new(CancellableScorer {
+            inner: self.inner.scorer(reader, boost)?,
+            token: self.token.clone(),
+            stopped: false,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        self.inner.explain(reader, doc)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> tantivy::Result<u32> {
+        self.check()?;
+        self.inner.count(reader)
+    }
+
+    fn for_each(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score),
+    ) -> tantivy::Result<()> {
+        self.check()?;
+        self.inner.for_each(reader, &mut |doc, score| {
+            if !self.token.load(Ordering::Relaxed) {
+                callback(doc, score);
+            }
+        })?;
+        self.check()
+    }
+
+    fn for_each_no_score(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(&[DocId]),
+    ) -> tantivy::Result<()> {
+        self.check()?;
+        self.inner.for_each_no_score(reader, &mut |docs| {
+            if !self.token.load(Ordering::Relaxed) {
+                callback(docs);
+            }
+        })?;
+        self.check()
+    }
+
+    fn for_each_pruning(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> tantivy::Result<()> {
+        self.check()?;
+        // 取消后把门槛抬到最高，剩下的文档都会被剪掉
+        self.inner
+            .for_each_pruning(threshold, reader, &mut |doc, score| {
+                if self.token.load(Ordering::Relaxed) {
+                    Score::MAX
+                } else {
+                    callback(doc, score)
+                }
+            })?;
+        self.check()
+    }
+}
+
+struct CancellableScorer {
+    inner: Box<dyn Scorer>,
+    token: Arc<AtomicBool>,
+    // 已因取消而提前结束，此后 doc() 恒为 TERMINATED
+    stopped: bool,
+}
+
+impl CancellableScorer {
+    fn cancelled(&mut self) -> bool {
+        self.stopped |= self.token.load(Ordering::Relaxed);
+        self.stopped
+    }
+}
+
+impl DocSet for CancellableScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cancelled() {
+            return TERMINATED;
+        }
+        self.inner.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.cancelled() {
+            return TERMINATED;
+        }
+        self.inner.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        if self.stopped {
+            return TERMINATED;
+        }
+        self.inner.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.inner.size_hint()
+    }
+}
+
+impl Scorer for CancellableScorer {
+    fn score(&mut self) -> Score {
+        self.inner.score()
+    }
+}
+
+/// Builds queries from the parsed syntax tree like `QueryParser` does, except that
+/// ranges over indexed text fields become [`CancellableRangeQuery`]: tantivy's version
+/// walks the whole term dictionary before the first document comes out.
+/// Subtrees without such a range are handed to the parser unchanged.
+pub struct CancellableQueryBuilder<'a> {
+    index: &'a Index,
+    query_parser: &'a QueryParser,
+    token: Arc<AtomicBool>,
+}
+
+impl<'a> CancellableQueryBuilder<'a> {
+    pub fn new(index: &'a Index, query_parser: &'a QueryParser, token: Arc<AtomicBool>) -> Self {
+        Self {
+            index,
+            query_parser,
+            token,
+        }
+    }
+
+    // 与 QueryParser 的宽松构建相同：出错的部分被丢弃并返回错误
+    pub fn build(&self, ast: UserInputAst) -> (Box<dyn Query>, Vec<QueryParserError>) {
+        if !self.has_text_range(&ast) {
+            return self
+                .query_parser
+                .build_query_from_user_input_ast_lenient(ast);
+        }
+        let mut errors = Vec::new();
+        let query = self
+            .build_ast(ast, &mut errors)
+            .unwrap_or_else(|| Box::new(EmptyQuery));
+        (query, errors)
+    }
+
+    // None 对应 QueryParser 中被裁掉的空子句
+    fn build_ast(
+        &self,
+        ast: UserInputAst,
+        errors: &mut Vec<QueryParserError>,
+    ) -> Option<Box<dyn Query>> {
+        if !self.has_text_range(&ast) {
+            let (query, mut sub_errors) = self
+                .query_parser
+                .build_query_from_user_input_ast_lenient(ast);
+            errors.append(&mut sub_errors);
+            return (!query.is::<EmptyQuery>()).then_some(query);
+        }
+        match ast {
+            UserInputAst::Clause(children) => {
+                let clauses: Vec<(Occur, Box<dyn Query>)> = children
+                    .into_iter()
+                    .filter_map(|(occur, child)| {
+                        // 未指定时与 QueryParser 的默认值一致（mytxt 未开启默认 AND）
+                        let occur = occur.unwrap_or(Occur::Should);
+                        self.build_ast(child, errors).map(|query| (occur, query))
+                    })
+                    .collect();
+                if clauses.is_empty() {
+                    return None;
+                }
+                Some(Box::new(BooleanQuery::new(clauses)))
+            }
+            UserInputAst::Boost(child, boost) => {
+                let query = self
+                    .build_ast(*child, errors)
+                    .unwrap_or_else(|| Box::new(EmptyQuery));
+                Some(Box::new(BoostQuery::new(query, boost as Score)))
+            }
+            UserInputAst::Leaf(leaf) => match *leaf {
+                UserInputLeaf::Range {
+                    field: Some(name),
+                    lower,
+                    upper,
+                } => {
+                    let field = self.text_field(&name)?;
+                    let lower = self.resolve_bound(field, &lower, errors);
+                    let upper = self.resolve_bound(field, &upper, errors);
+                    if lower == Bound::Unbounded && upper == Bound::Unbounded {
+                        return None;
+                    }
+                    Some(Box::new(CancellableRangeQuery {
+                        field,
+                        lower,
+                        upper,
+                        token: self.token.clone(),
+                    }))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn has_text_range(&self, ast: &UserInputAst) -> bool {
+        match ast {
+            UserInputAst::Clause(children) => {
+                children.iter().any(|(_, child)| self.has_text_range(child))
+            }
+            UserInputAst::Boost(child, _) => self.has_text_range(child),
+            UserInputAst::Leaf(leaf) => match leaf.as_ref() {
+                UserInputLeaf::Range {
+                    field: Some(name), ..
+                } => self.text_field(name).is_some(),
+                _ => false,
+            },
+        }
+    }
+
+    // 走倒排索引的文本字段；快速字段上的范围查询不遍历词典，仍由 QueryParser 处理
+    fn text_field(&self, name: &str) -> Option<Field> {
+        let schema = self.index.schema();
+        let field = schema.get_field(name).ok()?;
+        match schema.get_field_entry(field).field_type() {
+            FieldType::Str(options) if options.get_indexing_options().is_some() => {
+                (!options.is_fast()).then_some(field)
+            }
+            _ => None,
+        }
+    }
+
+    // 与 QueryParser 相同：边界要按字段的分词器切分，且只能得到一个词
+    fn resolve_bound(
+        &self,
+        field: Field,
+        bound: &UserInputBound,
+        errors: &mut Vec<QueryParserError>,
+    ) -> Bound<String> {
+        let text = bound.term_str();
+        if text == "*" {
+            return Bound::Unbounded;
+        }
+        let schema = self.index.schema();
+        let entry = schema.get_field_entry(field);
+        let tokenizer_name = match entry.field_type() {
+            FieldType::Str(options) => options
+                .get_indexing_options()
+                .map(|indexing| indexing.tokenizer().to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+        let Some(mut analyzer) = self.index.tokenizers().get(&tokenizer_name) else {
+            errors.push(QueryParserError::UnknownTokenizer {
+                field: entry.name().to_string(),
+                tokenizer: tokenizer_name,
+            });
+            return Bound::Unbounded;
+        };
+        let mut tokens = Vec::new();
+        analyzer
+            .token_stream(text)
+            .process(&mut |token| tokens.push(token.text.clone()));
+        if tokens.len() != 1 {
+            errors.push(QueryParserError::UnsupportedQuery(format!(
+                "Range query boundary cannot have multiple tokens: {text:?} {tokens:?}."
+            )));
+            return Bound::Unbounded;
+        }
+        let term = tokens.swap_remove(0);
+        match bound {
+            UserInputBound::Exclusive(_) => Bound::Excluded(term),
+            _ => Bound::Included(term),
+        }
+    }
+}
+
+/// Range over an indexed text field that checks the cancel token while it walks
+/// the term dictionary, so a broad range such as `content:[a TO z]` stops promptly.
+#[derive(Clone, Debug)]
+pub struct CancellableRangeQuery {
+    field: Field,
+    lower: Bound<String>,
+    upper: Bound<String>,
+    token: Arc<AtomicBool>,
+}
+
+impl Query for CancellableRangeQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(CancellableRangeWeight {
+            query: self.clone(),
+        }))
+    }
+}
+
+struct CancellableRangeWeight {
+    query: CancellableRangeQuery,
+}
+
+impl Weight for CancellableRangeWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let query = &self.query;
+        let inverted_index = reader.inverted_index(query.field)?;
+        let mut range = inverted_index.terms().range();
+        range = match &query.lower {
+            Bound::Included(term) => range.ge(term),
+            Bound::Excluded(term) => range.gt(term),
+            Bound::Unbounded => range,
+        };
+        range = match &query.upper {
+            Bound::Included(term) => range.le(term),
+            Bound::Excluded(term) => range.lt(term),
+            Bound::Unbounded => range,
+        };
+        let mut terms = range.into_stream()?;
+
+        let mut docs = BitSet::with_max_value(reader.max_doc());
+        while terms.advance() {
+            if query.token.load(Ordering::Relaxed) {
+                return Err(cancelled_error());
+            }
+            let mut postings = inverted_index
+                .read_block_postings_from_terminfo(terms.value(), IndexRecordOption::Basic)?;
+            while !postings.docs().is_empty() {
+                for &doc in postings.docs() {
+                    docs.insert(doc);
+                }
+                postings.advance();
+            }
+        }
+        Ok(Box::new(ConstScorer::new(BitSetDocSet::from(docs), boost)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("CancellableRangeQuery", 1.0))
+    }
+}
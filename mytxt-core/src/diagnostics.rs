@@ -0,0 +1,166 @@
+use std::ops::Range;
+use tantivy::query::{Query, QueryParser, QueryParserError};
+use tantivy::query_grammar::{self, LenientError, UserInputAst};
+
+// 查询语句中的一个问题，span 为出错部分在查询字符串中的字节范围
+#[derive(Debug, Clone)]
+pub struct QueryIssue {
+    pub message: String,
+    pub span: Option<Range<usize>>,
+}
+
+// Strict parsing: any problem aborts the search and is reported with its position.
+pub fn parse_strict(
+    query_parser: &QueryParser,
+    query_str: &str,
+) -> Result<Box<dyn Query>, Vec<QueryIssue>> {
+    parse_strict_with(query_str, |ast| {
+        query_parser.build_query_from_user_input_ast_lenient(ast)
+    })
+}
+
+// Lenient parsing: broken parts are dropped and returned as issues.
+pub fn parse_lenient(
+    query_parser: &QueryParser,
+    query_str: &str,
+) -> (Box<dyn Query>, Vec<QueryIssue>) {
+    parse_lenient_with(query_str, |ast| {
+        query_parser.build_query_from_user_input_ast_lenient(ast)
+    })
+}
+
+// 同 parse_strict，但由 build 把语法树转成查询（例如 CancellableQueryBuilder）
+pub fn parse_strict_with(
+    query_str: &str,
+    build: impl FnOnce(UserInputAst) -> (Box<dyn Query>, Vec<QueryParserError>),
+) -> Result<Box<dyn Query>, Vec<QueryIssue>> {
+    // tantivy only reports the whole query string on a syntax error, so re-run
+    // the recovering grammar to find out where it went wrong.
+    let ast = query_grammar::parse_query(query_str).map_err(|_| syntax_issues(query_str))?;
+    let (query, errors) = build(ast);
+    match errors.first() {
+        None => Ok(query),
+        Some(e) => Err(vec![from_parser_error(query_str, e)]),
+    }
+}
+
+// 同 parse_lenient，但由 build 把语法树转成查询
+pub fn parse_lenient_with(
+    query_str: &str,
+    build: impl FnOnce(UserInputAst) -> (Box<dyn Query>, Vec<QueryParserError>),
+) -> (Box<dyn Query>, Vec<QueryIssue>) {
+    let (ast, grammar_errors) = query_grammar::parse_query_lenient(query_str);
+    let (query, parser_errors) = build(ast);
+    let issues = grammar_errors
+        .iter()
+        .map(|e| from_grammar_error(query_str, e))
+        .chain(
+            parser_errors
+                .iter()
+                .map(|e| from_parser_error(query_str, e)),
+        )
+        .collect();
+    (query, issues)
+}
+
+fn syntax_issues(query_str: &str) -> Vec<QueryIssue> {
+    let (_, grammar_errors) = query_grammar::parse_query_lenient(query_str);
+    let mut issues: Vec<QueryIssue> = grammar_errors
+        .iter()
+        .map(|e| from_grammar_error(query_str, e))
+        .collect();
+    // The recovering grammar silently accepts some inputs the strict one rejects,
+    // most commonly a dangling boolean operator ("a AND", "a OR OR b").
+    if issues.is_empty() {
+        issues.extend(dangling_operator(query_str).map(|span| QueryIssue {
+            message: "布尔运算符缺少操作数".to_string(),
+            span: Some(span),
+        }));
+    }
+    if issues.is_empty() {
+        issues.push(QueryIssue {
+            message: "无法解析的查询语句".to_string(),
+            span: None,
+        });
+    }
+    issues
+}
+
+fn from_grammar_error(query_str: &str, error: &LenientError) -> QueryIssue {
+    QueryIssue {
+        message: error.message.clone(),
+        span: grammar_error_span(query_str, error),
+    }
+}
+
+fn from_parser_error(query_str: &str, error: &QueryParserError) -> QueryIssue {
+    let span = match error {
+        QueryParserError::FieldDoesNotExist(field)
+        | QueryParserError::FieldNotIndexed(field)
+        | QueryParserError::FieldDoesNotHavePositionsIndexed(field) => {
+            let needle = format!("{}:", field);
+            query_str
+                .find(&needle)
+                .map(|start| start..start + needle.len())
+        }
+        _ => None,
+    };
+    QueryIssue {
+        message: error.to_string(),
+        span,
+    }
+}
+
+fn grammar_error_span(query_str: &str, error: &LenientError) -> Option<Range<usize>> {
+    let pos = error.pos.min(query_str.len());
+    // An unclosed quote or bracket is reported at the end of the query;
+    // point at the opening delimiter instead.
+    let opener = if error.message.starts_with("missing delimiter") {
+        error.message.chars().last()
+    } else if error.message == "expected ')'" {
+        Some('(')
+    } else {
+        None
+    };
+    if let Some(opener) = opener {
+        if let Some(start) = query_str[..pos].rfind(opener) {
+            return Some(start..start + opener.len_utf8());
+        }
+    }
+    if error.message.contains("boolean operator") {
+        if let Some(span) = dangling_operator(query_str) {
+            return Some(span);
+        }
+    }
+    word_span(query_str, pos)
+}
+
+// The whitespace-delimited word at `pos`, or the last word when `pos` is at the end.
+fn word_span(query_str: &str, pos: usize) -> Option<Range<usize>> {
+    let (start, end) = if pos < query_str.len() {
+        let end = query_str[pos..]
+            .find(char::is_whitespace)
+            .map_or(query_str.len(), |i| pos + i);
+        (pos, end)
+    } else {
+        let end = query_str.trim_end().len();
+        let start = query_str[..end]
+            .trim_end_matches(|c: char| !c.is_whitespace())
+            .len();
+        (start, end)
+    };
+    (start < end).then_some(start..end)
+}
+
+fn dangling_operator(query_str: &str) -> Option<Range<usize>> {
+    let words: Vec<(usize, &str)> = query_str
+        .split_whitespace()
+        .map(|w| (w.as_ptr() as usize - query_str.as_ptr() as usize, w))
+        .collect();
+    let is_operator = |w: &str| w == "AND" || w == "OR";
+    words.iter().enumerate().find_map(|(i, &(start, word))| {
+        let dangling =
+            is_operator(word) && (i == 0 || i + 1 == words.len() || is_operator(words[i - 1].1));
+        dangling.then_some(start..start + word.len())
+    })
+}
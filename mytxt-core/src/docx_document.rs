@@ -0,0 +1,252 @@
+use anyhow::Result;
+use docx_rust::document::{
+    BodyContent, Paragraph as DocxParagraph, ParagraphContent, Run, RunContent, TableCellContent,
+    TableRowContent,
+};
+use docx_rust::formatting::CharacterProperty;
+use docx_rust::{Docx, DocxFile};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+// 预览用的 docx 文档结构。所有文字按顺序拼在 text 中（每段以 '\n' 结尾），
+// 各段落只记录自己在 text 中的字节范围，这样命中位置可以直接对应到段落上。
+pub struct FormattedDocument {
+    pub text: String,
+    pub blocks: Vec<Block>,
+}
+
+pub enum Block {
+    Paragraph(Paragraph),
+    // 行 -> 单元格 -> 单元格内的段落
+    Table(Vec<Vec<Vec<Paragraph>>>),
+}
+
+pub struct Paragraph {
+    pub kind: ParagraphKind,
+    // 不含结尾的 '\n'
+    pub range: Range<usize>,
+    pub spans: Vec<Span>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParagraphKind {
+    Body,
+    // 1 为最高一级标题
+    Heading(u8),
+    ListItem {
+        level: usize,
+        // None 表示项目符号列表
+        number: Option<usize>,
+    },
+}
+
+// 一段格式相同的文字
+pub struct Span {
+    pub range: Range<usize>,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+pub fn load(path: &Path) -> Result<FormattedDocument> {
+    let docx = DocxFile::from_file(path)?;
+    let mut docx = docx.parse()?;
+    let mut builder = Builder::new(&docx);
+
+    for content in std::mem::take(&mut docx.document.body.content) {
+        match content {
+            BodyContent::Paragraph(p) => {
+                let paragraph = builder.paragraph(&p);
+                builder.blocks.push(Block::Paragraph(paragraph));
+            }
+            BodyContent::Table(table) => {
+                let mut rows = Vec::new();
+                for row in &table.rows {
+                    let mut cells = Vec::new();
+                    for cell in &row.cells {
+                        if let TableRowContent::TableCell(cell) = cell {
+                            let paragraphs = cell
+                                .content
+                                .iter()
+                                .map(|TableCellContent::Paragraph(p)| builder.paragraph(p))
+                                .collect();
+                            cells.push(paragraphs);
+                        }
+                    }
+                    rows.push(cells);
+                }
+                builder.blocks.push(Block::Table(rows));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(FormattedDocument {
+        text: builder.text,
+        blocks: builder.blocks,
+    })
+}
+
+struct Builder {
+    text: String,
+    blocks: Vec<Block>,
+    // styleId -> 样式名，用来识别 "heading 1" / "标题 1" 这类标题样式
+    style_names: HashMap<String, String>,
+    // (numId, 级别) -> 是否为项目符号
+    bullets: HashMap<(isize, isize), bool>,
+    // 每个编号列表各级别当前的序号
+    counters: HashMap<isize, Vec<usize>>,
+}
+
+impl Builder {
+    fn new(docx: &Docx) -> Self {
+        let style_names = docx
+            .styles
+            .styles
+            .iter()
+            .filter_map(|s| Some((s.style_id.to_string(), s.name.as_ref()?.value.to_string())))
+            .collect();
+
+        let mut bullets = HashMap::new();
+        if let Some(numbering) = &docx.numbering {
+            for num in &numbering.numberings {
+                let (Some(num_id), Some(abstract_id)) = (
+                    num.num_id,
+                    num.abstract_num_id.as_ref().and_then(|a| a.value),
+                ) else {
+                    continue;
+                };
+                let Some(abstract_num) = numbering
+                    .abstract_numberings
+                    .iter()
+                    .find(|a| a.abstract_num_id == Some(abstract_id))
+                else {
+                    continue;
+                };
+                for level in &abstract_num.levels {
+                    let is_bullet = level
+                        .number_format
+                        .as_ref()
+                        .is_some_and(|f| f.value == "bullet");
+                    bullets.insert((num_id, level.i_level.unwrap_or(0)), is_bullet);
+                }
+            }
+        }
+
+        Self {
+            text: String::new(),
+            blocks: Vec::new(),
+            style_names,
+            bullets,
+            counters: HashMap::new(),
+        }
+    }
+
+    fn paragraph(&mut self, p: &DocxParagraph) -> Paragraph {
+        let kind = self.paragraph_kind(p);
+        let start = self.text.len();
+        let mut spans = Vec::new();
+        for content in &p.content {
+            match content {
+                ParagraphContent::Run(run) => self.run(run, &mut spans),
+                ParagraphContent::Link(link) => {
+                    if let Some(run) = &link.content {
+                        self.run(run, &mut spans);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let range = start..self.text.len();
+        self.text.push('\n');
+        Paragraph { kind, range, spans }
+    }
+
+    fn run(&mut self, run: &Run, spans: &mut Vec<Span>) {
+        let start = self.text.len();
+        for content in &run.content {
+            match content {
+                RunContent::Text(t) => self.text.push_str(&t.text),
+                RunContent::Tab(_) => self.text.push('\t'),
+                _ => {}
+            }
+        }
+        if self.text.len() == start {
+            return;
+        }
+        let (bold, italic) = run.property.as_ref().map_or((false, false), emphasis);
+        // 与前一段格式相同则合并
+        match spans.last_mut() {
+            Some(last) if last.range.end == start && last.bold == bold && last.italic == italic => {
+                last.range.end = self.text.len();
+            }
+            _ => spans.push(Span {
+                range: start..self.text.len(),
+                bold,
+                italic,
+            }),
+        }
+    }
+
+    fn paragraph_kind(&mut self, p: &DocxParagraph) -> ParagraphKind {
+        let Some(property) = &p.property else {
+            return ParagraphKind::Body;
+        };
+
+        if let Some(style_id) = &property.style_id {
+            let name = self
+                .style_names
+                .get(style_id.value.as_ref())
+                .map(String::as_str)
+                .unwrap_or(style_id.value.as_ref());
+            if let Some(level) = heading_level(name).or_else(|| heading_level(&style_id.value)) {
+                return ParagraphKind::Heading(level);
+            }
+        }
+
+        if let Some(numbering) = &property.numbering {
+            if let Some(num_id) = numbering.id.as_ref().map(|id| id.value) {
+                let level = numbering.level.as_ref().map_or(0, |l| l.value);
+                let depth = level.max(0) as usize;
+                let is_bullet = self.bullets.get(&(num_id, level)).copied().unwrap_or(true);
+                let counters = self.counters.entry(num_id).or_default();
+                // 进入某一级时，更深的级别重新计数
+                counters.resize(depth + 1, 0);
+                counters[depth] += 1;
+                return ParagraphKind::ListItem {
+                    level: depth,
+                    number: (!is_bullet).then_some(counters[depth]),
+                };
+            }
+        }
+
+        ParagraphKind::Body
+    }
+}
+
+fn emphasis(property: &CharacterProperty) -> (bool, bool) {
+    let bold = property
+        .bold
+        .as_ref()
+        .is_some_and(|b| b.value != Some(false));
+    let italic = property
+        .italics
+        .as_ref()
+        .is_some_and(|i| i.value != Some(false));
+    (bold, italic)
+}
+
+// "heading 2"、"Heading2"、"标题 2" -> 2；"Title"、"标题" -> 1
+fn heading_level(name: &str) -> Option<u8> {
+    let lower = name.to_lowercase();
+    if lower == "title" || lower == "标题" {
+        return Some(1);
+    }
+    let rest = lower
+        .strip_prefix("heading")
+        .or_else(|| lower.strip_prefix("标题"))?;
+    rest.trim()
+        .parse::<u8>()
+        .ok()
+        .filter(|l| (1..=9).contains(l))
+}
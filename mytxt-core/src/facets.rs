@@ -0,0 +1,272 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tantivy::collector::{FacetCollector, FacetCounts};
+use tantivy::query::{BooleanQuery, ConstScoreQuery, Occur, Query, TermQuery};
+use tantivy::schema::{Facet, IndexRecordOption, Schema};
+use tantivy::{DateTime, Term};
+
+// 侧栏中每个维度最多列出的项数
+const MAX_FACET_VALUES: usize = 20;
+
+// 结果分布统计的维度，每个维度对应索引中的一个 facet 字段
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FacetField {
+    // 相对索引根目录的文件夹层级
+    Folder,
+    // 小写扩展名
+    FileType,
+    // 修改年份
+    Year,
+}
+
+impl FacetField {
+    pub const ALL: [FacetField; 3] = [FacetField::Folder, FacetField::FileType, FacetField::Year];
+
+    pub fn field_name(self) -> &'static str {
+        match self {
+            FacetField::Folder => "folder",
+            FacetField::FileType => "file_type",
+            FacetField::Year => "year",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FacetField::Folder => "文件夹",
+            FacetField::FileType => "文件类型",
+            FacetField::Year => "修改年份",
+        }
+    }
+
+    // 各字段的值都挂在以字段名命名的顶层节点下（如 /folder/项目/2024）。
+    // tantivy 以根节点统计时会把多层路径的子项算错，所以不直接用根节点
+    fn root(self) -> Facet {
+        Facet::from_path([self.field_name()])
+    }
+
+    fn facet(self, components: impl IntoIterator<Item = String>) -> Facet {
+        Facet::from_path(std::iter::once(self.field_name().to_string()).chain(components))
+    }
+}
+
+// 侧栏中点选的筛选条件，facet 为完整路径（如 /folder/项目/2024）
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct FacetFilter {
+    pub field: FacetField,
+    pub facet: String,
+}
+
+impl FacetFilter {
+    // 命令行中的筛选条件：field 为字段名，value 为 "项目/2024" 这样的路径
+    pub fn parse(field: &str, value: &str) -> Option<Self> {
+        let field = FacetField::ALL
+            .into_iter()
+            .find(|f| f.field_name() == field)?;
+        // 索引中的扩展名都是小写
+        let value = match field {
+            FacetField::FileType => value.to_lowercase(),
+            _ => value.to_string(),
+        };
+        let components: Vec<String> = value
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect();
+        if components.is_empty() {
+            return None;
+        }
+        Some(Self {
+            field,
+            facet: field.facet(components).to_string(),
+        })
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}: {}", self.field.label(), facet_label(&self.facet))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FacetCount {
+    pub facet: String,
+    pub count: u64,
+}
+
+// 一个维度下的统计结果；文件夹维度在已筛选某个文件夹时统计它的子文件夹
+#[derive(Clone, Debug)]
+pub struct FacetSummary {
+    pub field: FacetField,
+    pub counts: Vec<FacetCount>,
+}
+
+// --- 建索引时使用 ---
+
+// 文件所在文件夹相对索引根目录的层级；直接位于根目录下的文件返回 None
+pub fn folder_facet(root: &Path, file: &Path) -> Option<Facet> {
+    let relative = file.parent()?.strip_prefix(root).ok()?;
+    let components: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    (!components.is_empty()).then(|| FacetField::Folder.facet(components))
+}
+
+pub fn file_type_facet(extension: &str) -> Facet {
+    FacetField::FileType.facet([extension.to_lowercase()])
+}
+
+pub fn year_facet(modified: DateTime) -> Facet {
+    FacetField::Year.facet([modified.into_utc().year().to_string()])
+}
+
+// --- 查询时使用 ---
+
+// 筛选条件以不计分的必须子句加到查询上，不影响原有的相关度排序
+pub fn apply_filters(
+    query: Box<dyn Query>,
+    filters: &[FacetFilter],
+    schema: &Schema,
+) -> Box<dyn Query> {
+    let mut clauses = vec![(Occur::Must, query)];
+    for filter in filters {
+        let Ok(field) = schema.get_field(filter.field.field_name()) else {
+            continue;
+        };
+        let Ok(facet) = Facet::from_text(&filter.facet) else {
+            continue;
+        };
+        let term = TermQuery::new(Term::from_facet(field, &facet), IndexRecordOption::Basic);
+        clauses.push((
+            Occur::Must,
+            Box::new(ConstScoreQuery::new(Box::new(term), 0.0)),
+        ));
+    }
+    if clauses.len() == 1 {
+        return clauses.pop().unwrap().1;
+    }
+    Box::new(BooleanQuery::new(clauses))
+}
+
+// 三个维度各一个收集器，旧索引中没有的字段为 None
+pub type FacetCollectors = (
+    Option<FacetCollector>,
+    Option<FacetCollector>,
+    Option<FacetCollector>,
+);
+
+pub fn collectors(schema: &Schema, filters: &[FacetFilter]) -> FacetCollectors {
+    let [folder, file_type, year] = FacetField::ALL.map(|field| {
+        schema.get_field(field.field_name()).ok()?;
+        let mut collector = FacetCollector::for_field(field.field_name());
+        collector.add_facet(parent_facet(field, filters));
+        Some(collector)
+    });
+    (folder, file_type, year)
+}
+
+pub fn summarize(
+    filters: &[FacetFilter],
+    counts: (
+        Option<FacetCounts>,
+        Option<FacetCounts>,
+        Option<FacetCounts>,
+    ),
+) -> Vec<FacetSummary> {
+    let (folder, file_type, year) = counts;
+    FacetField::ALL
+        .into_iter()
+        .zip([folder, file_type, year])
+        .filter_map(|(field, counts)| {
+            let counts = counts?;
+            let mut values: Vec<FacetCount> = counts
+                .top_k(parent_facet(field, filters), MAX_FACET_VALUES)
+                .into_iter()
+                .map(|(facet, count)| FacetCount {
+                    facet: facet.to_string(),
+                    count,
+                })
+                .collect();
+            // 年份按时间倒序，其余按数量
+            if field == FacetField::Year {
+                values.sort_by(|a, b| b.facet.cmp(&a.facet));
+            }
+            Some(FacetSummary {
+                field,
+                counts: values,
+            })
+        })
+        .collect()
+}
+
+// 统计哪一层的子项：文件夹维度已筛选时向下钻取一层，其余维度从顶层开始
+fn parent_facet(field: FacetField, filters: &[FacetFilter]) -> Facet {
+    let drill_down = field == FacetField::Folder;
+    filters
+        .iter()
+        .rev()
+        .find(|f| drill_down && f.field == field)
+        .and_then(|f| Facet::from_text(&f.facet).ok())
+        .unwrap_or_else(|| field.root())
+}
+
+// /folder/项目/2024 -> 2024
+pub fn facet_label(facet: &str) -> String {
+    Facet::from_text(facet)
+        .ok()
+        .and_then(|f| f.to_path().last().map(|s| s.to_string()))
+        .unwrap_or_else(|| facet.to_string())
+}
+
+// /folder/项目/2024 -> 项目/2024
+pub fn facet_path(facet: &str) -> String {
+    Facet::from_text(facet)
+        .ok()
+        .map(|f| {
+            f.to_path()
+                .into_iter()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_else(|| facet.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facet(field: &str, value: &str) -> Option<String> {
+        FacetFilter::parse(field, value).map(|filter| filter.facet)
+    }
+
+    #[test]
+    fn parse_builds_facet_paths() {
+        assert_eq!(
+            facet("folder", "项目/2024").as_deref(),
+            Some("/folder/项目/2024")
+        );
+        assert_eq!(facet("year", "/2024/").as_deref(), Some("/year/2024"));
+        // 索引中的扩展名是小写
+        assert_eq!(
+            facet("file_type", "DOCX").as_deref(),
+            Some("/file_type/docx")
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unknown_fields_and_empty_values() {
+        assert_eq!(facet("author", "张三"), None);
+        assert_eq!(facet("folder", ""), None);
+        assert_eq!(facet("folder", "//"), None);
+    }
+
+    #[test]
+    fn parsed_filter_keeps_its_field() {
+        let filter = FacetFilter::parse("folder", "项目").unwrap();
+        assert_eq!(filter.field, FacetField::Folder);
+        assert_eq!(
+            filter.label(),
+            format!("{}: 项目", FacetField::Folder.label())
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::docx_document::{self, FormattedDocument};
 use anyhow::Result;
 use docx_rust::document::{BodyContent, ParagraphContent, RunContent};
 use docx_rust::DocxFile;
@@ -34,4 +35,15 @@ pub fn read_file_content(path: &Path) -> Result<String> {
 
     // Fallback for plain text files
     std::fs::read_to_string(path).map_err(anyhow::Error::from)
-}
\ No newline at end of file
+}
+
+// 预览窗口用：docx 额外返回带格式的文档结构，其 text 即为纯文本内容
+pub fn read_preview(path: &Path) -> Result<(String, Option<FormattedDocument>)> {
+    if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
+        if ext.eq_ignore_ascii_case("docx") {
+            let document = docx_document::load(path)?;
+            return Ok((document.text.clone(), Some(document)));
+        }
+    }
+    Ok((read_file_content(path)?, None))
+}
@@ -0,0 +1,174 @@
+use crate::diagnostics;
+use crate::query::SearchOptions;
+use anyhow::{Context, Result};
+use regex::RegexBuilder;
+use std::collections::HashSet;
+use std::ops::Range;
+use tantivy::query::QueryParser;
+use tantivy::snippet::{collapse_overlapped_ranges, Snippet};
+use tantivy::Index;
+
+// 用索引时的分词器切分全文，返回与查询词相同的词元所在的字节范围。
+// This is what the snippet generator does internally, so the preview highlights
+// exactly what tantivy matched, including normalized and synonym hits.
+pub fn match_ranges(
+    index: &Index,
+    content: &str,
+    query_str: &str,
+    options: &SearchOptions,
+) -> Result<Vec<Range<usize>>> {
+    let content_field = index
+        .schema()
+        .get_field("content")
+        .context("Schema error: 'content' field not found")?;
+
+    let query_parser = QueryParser::for_index(index, vec![content_field]);
+    let (mut query, _ignored) = diagnostics::parse_lenient(&query_parser, query_str);
+    if let Some(synonyms) = &options.synonyms {
+        query = synonyms.expand(query, &query_parser);
+    }
+
+    let mut terms: HashSet<String> = HashSet::new();
+    query.query_terms(&mut |term, _| {
+        if term.field() == content_field {
+            if let Some(text) = term.value().as_str() {
+                terms.insert(text.to_string());
+            }
+        }
+    });
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut analyzer = index.tokenizer_for_field(content_field)?;
+    let mut token_stream = analyzer.token_stream(content);
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    while let Some(token) = token_stream.next() {
+        if terms.contains(&token.text) {
+            ranges.push(token.offset_from..token.offset_to);
+        }
+    }
+
+    // jieba 的搜索模式会产生重叠的词元，合并成不重叠的区间
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    Ok(merged)
+}
+
+// 预览窗口内查找框的选项，与主查询无关
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FindOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+}
+
+// Plain text search used by the find bar; the pattern is escaped unless regex is on.
+pub fn find_ranges(
+    content: &str,
+    pattern: &str,
+    options: FindOptions,
+) -> Result<Vec<Range<usize>>, regex::Error> {
+    let mut pattern = if options.regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    if options.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    let re = RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()?;
+    Ok(re
+        .find_iter(content)
+        .filter(|m| !m.is_empty())
+        .map(|m| m.range())
+        .collect())
+}
+
+// 结果摘要：纯文本及其中需要高亮的字节范围，在结果到达时解析一次
+#[derive(Clone, Debug, Default)]
+pub struct HighlightedText {
+    pub text: String,
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl HighlightedText {
+    // 直接取片段原文和高亮区间，不经过 html 转义
+    pub fn from_snippet(snippet: &Snippet) -> Self {
+        Self {
+            // 换行换成等长的空格，高亮区间不变，摘要在列表中保持紧凑
+            text: snippet.fragment().replace(['\n', '\r'], " "),
+            highlights: collapse_overlapped_ranges(snippet.highlighted()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{self, AnalyzerKind, AnalyzerOptions};
+    use crate::testing;
+    use tantivy::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions};
+
+    fn index(kind: AnalyzerKind) -> Index {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field(
+            "content",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(kind.tokenizer_name())
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let options = AnalyzerOptions {
+            kind,
+            ..testing::analyzer_options()
+        };
+        analyzer::register_tokenizers(&index, &options);
+        index
+    }
+
+    fn matched<'a>(index: &Index, content: &'a str, query: &str) -> Vec<&'a str> {
+        match_ranges(index, content, query, &testing::search_options())
+            .unwrap()
+            .into_iter()
+            .map(|range| &content[range])
+            .collect()
+    }
+
+    #[test]
+    fn finds_every_occurrence_in_order() {
+        let index = index(AnalyzerKind::JiebaNormalized);
+        let content = "测试报告\n第二次测试";
+        assert_eq!(matched(&index, content, "测试"), ["测试", "测试"]);
+    }
+
+    #[test]
+    fn matches_normalized_forms() {
+        let index = index(AnalyzerKind::JiebaNormalized);
+        let content = "ＡＢＣ 与 Abc";
+        assert_eq!(matched(&index, content, "abc"), ["ＡＢＣ", "Abc"]);
+    }
+
+    #[test]
+    fn plain_jieba_does_not_fold_width() {
+        let index = index(AnalyzerKind::Jieba);
+        assert_eq!(matched(&index, "ＡＢＣ 与 abc", "abc"), ["abc"]);
+    }
+
+    #[test]
+    fn no_terms_means_no_matches() {
+        let index = index(AnalyzerKind::JiebaNormalized);
+        assert!(matched(&index, "测试报告", "苹果").is_empty());
+        assert!(matched(&index, "测试报告", "").is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::analyzer::{self, AnalyzerOptions};
+use crate::facets::{self, FacetField};
+use crate::file_utils;
+use crate::query::Searcher;
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+use tantivy::directory::MmapDirectory;
+use tantivy::schema::*;
+use tantivy::{doc, DateTime, Index, IndexSettings, TantivyDocument, TantivyError, Term};
+use walkdir::WalkDir;
+
+// 建立索引时的进度回调，默认什么也不做
+pub trait IndexObserver {
+    // 每处理完一个 docx 文件调用一次
+    fn progress(&self, _done: usize, _total: usize) {}
+    // 无法读取的文件会被跳过，其余文件继续索引
+    fn file_failed(&self, _path: &Path, _error: &anyhow::Error) {}
+}
+
+// 不关心进度时传 &()
+impl IndexObserver for () {}
+
+// 保存完整路径（不分词）的字段名，旧索引中可能没有
+pub const PATH_KEY: &str = "path_key";
+
+// 磁盘上的一个索引目录及建立它所用的分词器
+pub struct Indexer {
+    index_dir: PathBuf,
+    analyzer_options: AnalyzerOptions,
+}
+
+impl Indexer {
+    pub fn new(index_dir: impl Into<PathBuf>, analyzer_options: AnalyzerOptions) -> Self {
+        Self {
+            index_dir: index_dir.into(),
+            analyzer_options,
+        }
+    }
+
+    pub fn index_dir(&self) -> &Path {
+        &self.index_dir
+    }
+
+    // 清空索引后重新索引 path 下的全部 docx 文件，完成后返回可用于搜索的 Searcher
+    pub fn index_directory(&self, path: &Path, observer: &dyn IndexObserver) -> Result<Searcher> {
+        index_directory(&self.index_dir, path, &self.analyzer_options, observer)
+    }
+
+    // 只更新变化的文件：按路径删除 changed 和 removed 的旧文档，再重新读取 changed 中的文件。
+    // 写锁只在处理这一批文件期间持有，不会长时间挡住其他地方的索引操作
+    pub fn update_files(
+        &self,
+        root: &Path,
+        changed: &[PathBuf],
+        removed: &[PathBuf],
+        observer: &dyn IndexObserver,
+    ) -> Result<Searcher> {
+        update_files(
+            &self.index_dir,
+            root,
+            changed,
+            removed,
+            &self.analyzer_options,
+            observer,
+        )
+    }
+
+    // 打开磁盘上已有的索引而不重新建立
+    pub fn open(&self) -> Result<Searcher> {
+        if !self.index_dir.join("meta.json").exists() {
+            bail!("索引不存在，请先建立索引: {}", self.index_dir.display());
+        }
+        let index = Index::open_in_dir(&self.index_dir)?;
+        analyzer::register_tokenizers(&index, &self.analyzer_options);
+        Searcher::open(index)
+    }
+}
+
+// 索引的各个字段
+struct IndexFields {
+    path: Field,
+    // 完整路径，不分词，用于按文件更新、删除和精确查找
+    path_key: Field,
+    root: Field,
+    extension: Field,
+    modified: Field,
+    folder: Field,
+    file_type: Field,
+    year: Field,
+    content: Field,
+}
+
+fn build_schema(analyzer_options: &AnalyzerOptions) -> (Schema, IndexFields) {
+    let mut schema_builder = Schema::builder();
+    let path = schema_builder.add_text_field("path", TEXT | STORED);
+    let path_key = schema_builder.add_text_field(PATH_KEY, STRING);
+    // 用于结果分组：索引根目录、扩展名、修改时间
+    let root = schema_builder.add_text_field("root", STRING | STORED);
+    let extension = schema_builder.add_text_field("extension", STRING | STORED | FAST);
+    let modified = schema_builder.add_date_field("modified", INDEXED | STORED | FAST);
+    // 侧栏的结果分布统计：文件夹层级、文件类型、修改年份
+    let folder =
+        schema_builder.add_facet_field(FacetField::Folder.field_name(), FacetOptions::default());
+    let file_type =
+        schema_builder.add_facet_field(FacetField::FileType.field_name(), FacetOptions::default());
+    let year = schema_builder.add_facet_field(FacetField::Year.field_name(), FacetOptions::default());
+
+    let text_indexing = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(analyzer_options.kind.tokenizer_name())
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+        .set_stored();
+    let content = schema_builder.add_text_field("content", text_indexing);
+    let fields = IndexFields {
+        path,
+        path_key,
+        root,
+        extension,
+        modified,
+        folder,
+        file_type,
+        year,
+        content,
+    };
+    (schema_builder.build(), fields)
+}
+
+// 只索引 docx 文件，跳过 Word 打开文档时生成的 ~$ 锁文件
+pub fn is_docx(path: &Path) -> bool {
+    let lock_file = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .is_some_and(|name| name.starts_with("~$"));
+    !lock_file
+        && path
+            .extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("docx"))
+}
+
+fn index_directory(
+    index_path: &Path,
+    path: &Path,
+    analyzer_options: &AnalyzerOptions,
+    observer: &dyn IndexObserver,
+) -> Result<Searcher> {
+    // 1. Count total files for progress tracking
+    let walker = WalkDir::new(path).into_iter();
+    let total_files = walker
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_docx(e.path()))
+        .count();
+    let mut processed_files = 0;
+
+    // 2. Setup Tantivy Index
+    std::fs::create_dir_all(index_path)?;
+    let directory = MmapDirectory::open(index_path)?;
+    let (schema, fields) = build_schema(analyzer_options);
+    let index = match Index::open_or_create(directory, schema.clone()) {
+        Ok(index) => index,
+        // The analyzer is part of the schema, so switching it means rebuilding from scratch.
+        Err(TantivyError::SchemaError(_)) => Index::create(
+            MmapDirectory::open(index_path)?,
+            schema.clone(),
+            IndexSettings::default(),
+        )?,
+        Err(e) => return Err(e.into()),
+    };
+    analyzer::register_tokenizers(&index, analyzer_options);
+    let mut index_writer = index.writer(50_000_000)?;
+    // Clear old index data
+    index_writer.delete_all_documents()?;
+
+    // 3. Process and index each file
+    for entry in WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_docx(e.path()))
+    {
+        let file_path = entry.path();
+        match file_document(&fields, path, file_path) {
+            Ok(Some(document)) => {
+                index_writer.add_document(document)?;
+            }
+            Ok(None) => {}
+            Err(e) => observer.file_failed(file_path, &e),
+        }
+        // 4. Update progress
+        processed_files += 1;
+        observer.progress(processed_files, total_files);
+    }
+
+    index_writer.commit()?;
+
+    // 5. Hand back a reader over the freshly committed index
+    Searcher::open(index)
+}
+
+fn update_files(
+    index_path: &Path,
+    root: &Path,
+    changed: &[PathBuf],
+    removed: &[PathBuf],
+    analyzer_options: &AnalyzerOptions,
+    observer: &dyn IndexObserver,
+) -> Result<Searcher> {
+    let (schema, fields) = build_schema(analyzer_options);
+    let index = match Index::open_or_create(MmapDirectory::open(index_path)?, schema) {
+        Ok(index) => index,
+        Err(TantivyError::SchemaError(_)) => {
+            bail!("索引的字段或分词设置与当前不一致，请先重新建立索引")
+        }
+        Err(e) => return Err(e.into()),
+    };
+    analyzer::register_tokenizers(&index, analyzer_options);
+    let mut index_writer = index.writer(50_000_000)?;
+    for file_path in changed.iter().chain(removed) {
+        index_writer.delete_term(Term::from_field_text(fields.path_key, path_str(file_path)));
+    }
+    for (i, file_path) in changed.iter().enumerate() {
+        match file_document(&fields, root, file_path) {
+            Ok(Some(document)) => {
+                index_writer.add_document(document)?;
+            }
+            Ok(None) => {}
+            Err(e) => observer.file_failed(file_path, &e),
+        }
+        observer.progress(i + 1, changed.len());
+    }
+    index_writer.commit()?;
+    Searcher::open(index)
+}
+
+fn path_str(path: &Path) -> &str {
+    path.to_str().unwrap_or_default()
+}
+
+// 读取一个 docx 文件生成文档，正文为空时返回 None
+fn file_document(
+    fields: &IndexFields,
+    root: &Path,
+    file_path: &Path,
+) -> Result<Option<TantivyDocument>> {
+    let content = file_utils::read_file_content(file_path)?;
+    if content.is_empty() {
+        return Ok(None);
+    }
+    let extension = file_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let mut document = doc!(
+        fields.path => path_str(file_path),
+        fields.path_key => path_str(file_path),
+        fields.root => path_str(root),
+        fields.extension => extension.to_lowercase(),
+        fields.content => content,
+        fields.file_type => facets::file_type_facet(extension)
+    );
+    if let Some(folder) = facets::folder_facet(root, file_path) {
+        document.add_facet(fields.folder, folder);
+    }
+    if let Some(modified) = modified_time(file_path) {
+        document.add_date(fields.modified, modified);
+        document.add_facet(fields.year, facets::year_facet(modified));
+    }
+    Ok(Some(document))
+}
+
+fn modified_time(path: &Path) -> Option<DateTime> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let secs = modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some(DateTime::from_timestamp_secs(secs as i64))
+}
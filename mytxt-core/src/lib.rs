@@ -0,0 +1,22 @@
+// mytxt 的索引和搜索核心：建立索引、执行查询、生成摘要和分布统计。
+// 进度通过 IndexObserver / SearchObserver 回调报告，不依赖任何界面或消息通道。
+pub mod analyzer;
+pub mod cancel;
+pub mod diagnostics;
+pub mod docx_document;
+pub mod facets;
+pub mod file_utils;
+pub mod highlight;
+pub mod indexer;
+pub mod query;
+pub mod service;
+pub mod synonyms;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use indexer::{IndexObserver, Indexer};
+pub use query::{
+    IndexStats, SearchError, SearchObserver, SearchOptions, SearchRequest, SearchResponse,
+    SearchResult, Searcher,
+};
+pub use service::{IndexService, IndexingGuard};
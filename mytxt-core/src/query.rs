@@ -0,0 +1,447 @@
+use crate::cancel::{CancellableQuery, CancellableQueryBuilder};
+use crate::diagnostics::{self, QueryIssue};
+use crate::facets::{self, FacetFilter, FacetSummary};
+use crate::highlight::{self, HighlightedText};
+use crate::indexer;
+use crate::synonyms::SynonymTable;
+use anyhow::{Context, Result};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tantivy::collector::{Collector, Count, TopDocs};
+use tantivy::query::{AllQuery, EnableScoring, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{
+    DateTime, DocAddress, Index, IndexReader, ReloadPolicy, Score, TantivyDocument, Term,
+};
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub path: String,
+    pub score: f32,
+    // 索引时的根目录、小写扩展名和修改时间，旧索引中可能没有
+    pub root: String,
+    pub extension: String,
+    pub modified: Option<DateTime>,
+    // 按相关度排列的摘要；流式搜索时先为空，稍后由 SearchObserver::snippets 补上
+    pub snippets: Vec<HighlightedText>,
+}
+
+// 流式搜索时两次发送中间结果的最短间隔
+const STREAM_INTERVAL: Duration = Duration::from_millis(50);
+
+// 每次搜索时从设置中取出的参数快照
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    // None 表示关闭同义词扩展
+    pub synonyms: Option<SynonymTable>,
+    // 宽松模式：跳过无法解析的部分而不是报错
+    pub lenient: bool,
+    // 最多返回多少条结果
+    pub limit: usize,
+    // 每条摘要的最大长度（字符）
+    pub snippet_length: usize,
+    // 每个文档最多生成几条摘要
+    pub snippets_per_result: usize,
+    // 侧栏中点选的筛选条件
+    pub filters: Vec<FacetFilter>,
+}
+
+// 一次搜索的输入：查询语句和参数
+#[derive(Clone, Debug)]
+pub struct SearchRequest {
+    pub query: String,
+    pub options: SearchOptions,
+}
+
+impl SearchRequest {
+    pub fn new(query: impl Into<String>, options: SearchOptions) -> Self {
+        Self {
+            query: query.into(),
+            options,
+        }
+    }
+}
+
+// 搜索完成后的全部结果，摘要已补全
+#[derive(Clone, Debug)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    // 全部命中（不受结果数上限影响）在各维度上的分布
+    pub facets: Vec<FacetSummary>,
+    pub duration: Duration,
+    // 宽松模式下被忽略的部分
+    pub ignored: Vec<QueryIssue>,
+}
+
+#[derive(Debug)]
+pub enum SearchError {
+    // 查询语句无法解析，搜索未执行
+    Query(Vec<QueryIssue>),
+    Cancelled,
+    Failed(anyhow::Error),
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::Query(issues) => {
+                let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+                write!(f, "查询语法错误: {}", messages.join("; "))
+            }
+            SearchError::Cancelled => write!(f, "搜索已取消"),
+            SearchError::Failed(e) => write!(f, "{:#}", e),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<anyhow::Error> for SearchError {
+    fn from(e: anyhow::Error) -> Self {
+        SearchError::Failed(e)
+    }
+}
+
+impl From<tantivy::TantivyError> for SearchError {
+    fn from(e: tantivy::TantivyError) -> Self {
+        SearchError::Failed(e.into())
+    }
+}
+
+// 搜索过程中的中间结果，默认什么也不做。
+// 界面用它流式显示结果；只要最终结果时传 &() 即可。
+pub trait SearchObserver {
+    // 目前为止的排名（尚无摘要），每次都是完整的列表
+    fn hits(&self, _hits: &[SearchResult]) {}
+    // 为最近一次 hits 中对应下标的结果补上摘要
+    fn snippets(&self, _snippets: &[(usize, Vec<HighlightedText>)]) {}
+    fn facets(&self, _facets: &[FacetSummary]) {}
+}
+
+impl SearchObserver for () {}
+
+// 索引的统计信息
+#[derive(Clone, Debug)]
+pub struct IndexStats {
+    pub num_docs: u64,
+    pub segments: usize,
+    // 全部文档在各维度上的分布
+    pub facets: Vec<FacetSummary>,
+}
+
+// 一个已打开索引的句柄，clone 开销很小，可以交给搜索线程。
+// 每次搜索开始时取一份快照，搜索期间索引被重建也不受影响。
+#[derive(Clone)]
+pub struct Searcher {
+    index: Index,
+    reader: IndexReader,
+}
+
+impl Searcher {
+    // 其他进程（如命令行的 watch）提交到同一索引后，reader 会自动切换到新的提交
+    pub(crate) fn open(index: Index) -> Result<Self> {
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()?;
+        Ok(Self { index, reader })
+    }
+
+    pub fn search(
+        &self,
+        request: &SearchRequest,
+        observer: &dyn SearchObserver,
+        cancel_token: Arc<AtomicBool>,
+    ) -> Result<SearchResponse, SearchError> {
+        search(&self.index, &self.reader, request, observer, cancel_token)
+    }
+
+    // 用索引时的分词器定位 content 中的命中词，供预览高亮
+    pub fn match_ranges(
+        &self,
+        content: &str,
+        query_str: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<Range<usize>>> {
+        highlight::match_ranges(&self.index, content, query_str, options)
+    }
+
+    // 建立索引时选择的根目录
+    pub fn roots(&self) -> Result<Vec<String>> {
+        let Ok(root_field) = self.index.schema().get_field("root") else {
+            return Ok(Vec::new());
+        };
+        let mut roots = BTreeSet::new();
+        for segment_reader in self.reader.searcher().segment_readers() {
+            let inverted_index = segment_reader.inverted_index(root_field)?;
+            let mut terms = inverted_index.terms().stream()?;
+            while terms.advance() {
+                if let Ok(root) = std::str::from_utf8(terms.key()) {
+                    roots.insert(root.to_string());
+                }
+            }
+        }
+        Ok(roots.into_iter().collect())
+    }
+
+    // path 是否为索引中的一个文件，按建立索引时记录的完整路径精确比较
+    pub fn contains_file(&self, path: &Path) -> Result<bool> {
+        // 旧索引没有 path_key 字段，需要重新建立索引后才能按路径查找
+        let Ok(path_key) = self.index.schema().get_field(indexer::PATH_KEY) else {
+            return Ok(false);
+        };
+        if !indexer::is_docx(path) {
+            return Ok(false);
+        }
+        let Some(path) = path.to_str() else {
+            return Ok(false);
+        };
+        let query = TermQuery::new(
+            Term::from_field_text(path_key, path),
+            IndexRecordOption::Basic,
+        );
+        Ok(self.reader.searcher().search(&query, &Count)? > 0)
+    }
+
+    pub fn stats(&self) -> Result<IndexStats> {
+        let searcher = self.reader.searcher();
+        let schema = self.index.schema();
+        let counts = searcher.search(&AllQuery, &facets::collectors(&schema, &[]))?;
+        Ok(IndexStats {
+            num_docs: searcher.num_docs(),
+            segments: searcher.segment_readers().len(),
+            facets: facets::summarize(&[], counts),
+        })
+    }
+}
+
+fn search(
+    index: &Index,
+    reader: &IndexReader,
+    request: &SearchRequest,
+    observer: &dyn SearchObserver,
+    cancel_token: Arc<AtomicBool>,
+) -> Result<SearchResponse, SearchError> {
+    let start_time = Instant::now();
+    let query_str = request.query.as_str();
+    let options = &request.options;
+
+    let searcher = reader.searcher();
+    let schema = index.schema();
+
+    let fields = ResultFields::new(&schema)?;
+    let content_field = schema
+        .get_field("content")
+        .context("Schema error: 'content' field not found")?;
+
+    let query_parser = QueryParser::for_index(index, vec![content_field]);
+    let mut ignored: Vec<QueryIssue> = Vec::new();
+    let builder = CancellableQueryBuilder::new(index, &query_parser, cancel_token.clone());
+    let mut query = if options.lenient {
+        let (query, issues) = diagnostics::parse_lenient_with(query_str, |ast| builder.build(ast));
+        ignored = issues;
+        query
+    } else {
+        diagnostics::parse_strict_with(query_str, |ast| builder.build(ast))
+            .map_err(SearchError::Query)?
+    };
+    if let Some(synonyms) = &options.synonyms {
+        // The snippet generator below sees the expanded query, so synonym hits are highlighted too.
+        query = synonyms.expand(query, &query_parser);
+    }
+    query = facets::apply_filters(query, &options.filters, &schema);
+
+    // 收集文档的过程中会检查取消标记，“停止”也能打断耗时的查询
+    let query = CancellableQuery::new(query, cancel_token.clone());
+    // 排名和各维度的分布统计在同一次遍历中收集
+    let collector = (
+        TopDocs::with_limit(options.limit.max(1)),
+        facets::collectors(&schema, &options.filters),
+    );
+    let weight = query.weight(EnableScoring::enabled_from_searcher(&searcher))?;
+
+    // 逐个段搜索，每搜完一段就把目前的排名先交给 observer
+    // 已读过的文档的结果字段，流式发送中间排名时复用
+    let mut cache: HashMap<DocAddress, SearchResult> = HashMap::new();
+    let mut segment_fruits = Vec::new();
+    let mut facet_fruits = Vec::new();
+    let mut last_sent = Instant::now();
+    let segment_readers = searcher.segment_readers();
+    for (segment_ord, segment_reader) in segment_readers.iter().enumerate() {
+        let fruit = collector.collect_segment(weight.as_ref(), segment_ord as u32, segment_reader);
+        if cancel_token.load(Ordering::SeqCst) {
+            return Err(SearchError::Cancelled);
+        }
+        let (top_fruit, facet_fruit) = fruit?;
+        segment_fruits.push(top_fruit);
+        facet_fruits.push(facet_fruit);
+
+        // 只在要发送时才合并目前的排名
+        let is_last = segment_ord + 1 == segment_readers.len();
+        if !is_last && last_sent.elapsed() >= STREAM_INTERVAL {
+            let top_docs = collector.0.merge_fruits(segment_fruits.clone())?;
+            observer.hits(&collect_hits(&searcher, &top_docs, &fields, &mut cache)?);
+            last_sent = Instant::now();
+        }
+    }
+    let top_docs = collector.0.merge_fruits(segment_fruits)?;
+
+    let facet_counts = collector.1.merge_fruits(facet_fruits)?;
+    let facets = facets::summarize(&options.filters, facet_counts);
+    observer.facets(&facets);
+
+    if top_docs.is_empty() {
+        return Ok(SearchResponse {
+            results: Vec::new(),
+            facets,
+            duration: start_time.elapsed(),
+            ignored,
+        });
+    }
+
+    // 最终排名已确定，先交给 observer，摘要随后分批补上
+    let mut results = collect_hits(&searcher, &top_docs, &fields, &mut cache)?;
+    drop(cache);
+    observer.hits(&results);
+
+    let mut snippet_generator = SnippetGenerator::create(&searcher, &query, content_field)?;
+    snippet_generator.set_max_num_chars(options.snippet_length);
+
+    let mut pending = Vec::new();
+    last_sent = Instant::now();
+    for (i, (_score, doc_address)) in top_docs.iter().enumerate() {
+        // Check for cancellation signal periodically.
+        if cancel_token.load(Ordering::SeqCst) {
+            return Err(SearchError::Cancelled);
+        }
+
+        // 正文只在这里为最终结果读取，不随中间结果缓存
+        let doc: TantivyDocument = searcher.doc(*doc_address)?;
+        let text = doc
+            .get_first(content_field)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        results[i].snippets = best_snippets(&snippet_generator, text, options.snippets_per_result);
+        pending.push((i, results[i].snippets.clone()));
+
+        if last_sent.elapsed() >= STREAM_INTERVAL {
+            observer.snippets(&std::mem::take(&mut pending));
+            last_sent = Instant::now();
+        }
+    }
+    // 最后一批不足一个间隔的摘要
+    if !pending.is_empty() {
+        observer.snippets(&pending);
+    }
+
+    Ok(SearchResponse {
+        results,
+        facets,
+        duration: start_time.elapsed(),
+        ignored,
+    })
+}
+
+// 结果中用到的存储字段；分组用的字段在旧索引中可能不存在
+struct ResultFields {
+    path: Field,
+    root: Option<Field>,
+    extension: Option<Field>,
+    modified: Option<Field>,
+}
+
+impl ResultFields {
+    fn new(schema: &Schema) -> Result<Self> {
+        Ok(Self {
+            path: schema
+                .get_field("path")
+                .context("Schema error: 'path' field not found")?,
+            root: schema.get_field("root").ok(),
+            extension: schema.get_field("extension").ok(),
+            modified: schema.get_field("modified").ok(),
+        })
+    }
+}
+
+// Turn ranked doc addresses into results without snippets.
+// Only the displayed fields are cached per document, so re-ranking between batches
+// reads each document once without keeping its content around.
+fn collect_hits(
+    searcher: &tantivy::Searcher,
+    top_docs: &[(Score, DocAddress)],
+    fields: &ResultFields,
+    cache: &mut HashMap<DocAddress, SearchResult>,
+) -> Result<Vec<SearchResult>> {
+    let mut hits = Vec::with_capacity(top_docs.len());
+    for (score, doc_address) in top_docs {
+        let hit = match cache.entry(*doc_address) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(load_hit(searcher, *doc_address, fields)?),
+        };
+        hits.push(SearchResult {
+            score: *score,
+            ..hit.clone()
+        });
+    }
+    Ok(hits)
+}
+
+// 读出一个文档并只保留结果列表用到的字段
+fn load_hit(
+    searcher: &tantivy::Searcher,
+    doc_address: DocAddress,
+    fields: &ResultFields,
+) -> Result<SearchResult> {
+    let doc: TantivyDocument = searcher.doc(doc_address)?;
+    let text = |field: Option<Field>| {
+        field
+            .and_then(|f| doc.get_first(f))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    Ok(SearchResult {
+        path: doc
+            .get_first(fields.path)
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown Path")
+            .to_string(),
+        score: 0.0,
+        root: text(fields.root),
+        extension: text(fields.extension),
+        modified: fields
+            .modified
+            .and_then(|f| doc.get_first(f))
+            .and_then(|v| v.as_datetime()),
+        snippets: Vec::new(),
+    })
+}
+
+// 取最相关的片段后把它从原文中挖掉再找下一段，得到互不重叠的多条摘要
+fn best_snippets(generator: &SnippetGenerator, text: &str, count: usize) -> Vec<HighlightedText> {
+    let mut snippets = Vec::new();
+    let mut remaining = text.to_string();
+    for _ in 0..count.max(1) {
+        let snippet = generator.snippet(&remaining);
+        if snippet.is_empty() {
+            // 没有任何命中时仍保留开头一段作为摘要
+            if snippets.is_empty() && !snippet.fragment().is_empty() {
+                snippets.push(HighlightedText::from_snippet(&snippet));
+            }
+            break;
+        }
+        snippets.push(HighlightedText::from_snippet(&snippet));
+        let Some(start) = remaining.find(snippet.fragment()) else {
+            break;
+        };
+        remaining.replace_range(start..start + snippet.fragment().len(), "\n");
+    }
+    snippets
+}
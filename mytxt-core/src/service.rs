@@ -0,0 +1,134 @@
+use crate::analyzer::AnalyzerOptions;
+use crate::indexer::{IndexObserver, Indexer};
+use crate::query::Searcher;
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+// 应用持有的全部已打开索引，按索引目录区分。
+// 锁只在取出或替换句柄时短暂持有：搜索拿到 Searcher 的 clone 后在自己的快照上进行，
+// 重建索引在写入期间不影响已有的搜索，提交后才替换句柄。
+#[derive(Default)]
+pub struct IndexService {
+    indexes: RwLock<HashMap<PathBuf, OpenIndex>>,
+    // 正在重建的索引目录，界面和 API 共用，同一目录同时只能有一个写入者
+    indexing: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+// 重建索引的占用标记，释放后同一目录才能再次开始重建
+pub struct IndexingGuard {
+    index_dir: PathBuf,
+    indexing: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl IndexingGuard {
+    pub fn index_dir(&self) -> &Path {
+        &self.index_dir
+    }
+}
+
+impl Drop for IndexingGuard {
+    fn drop(&mut self) {
+        self.indexing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.index_dir);
+    }
+}
+
+// 句柄上注册的分词器来自打开时的分词设置，设置变化后需要重新打开
+struct OpenIndex {
+    analyzer_options: AnalyzerOptions,
+    searcher: Searcher,
+}
+
+impl IndexService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 已打开的索引
+    pub fn get(&self, index_dir: &Path) -> Option<Searcher> {
+        self.read()
+            .get(index_dir)
+            .map(|index| index.searcher.clone())
+    }
+
+    // 已用相同的分词设置打开时直接返回，否则从磁盘打开磁盘上已有的索引
+    pub fn open(&self, index_dir: &Path, analyzer_options: &AnalyzerOptions) -> Result<Searcher> {
+        if let Some(index) = self.read().get(index_dir) {
+            if index.analyzer_options == *analyzer_options {
+                return Ok(index.searcher.clone());
+            }
+        }
+        // 打开时会加载分词词典，比较慢，不持有锁
+        let searcher = Indexer::new(index_dir, analyzer_options.clone()).open()?;
+        Ok(self.insert(index_dir, analyzer_options, searcher))
+    }
+
+    // 开始重建 index_dir，该目录已在重建时返回 None
+    pub fn begin_indexing(&self, index_dir: &Path) -> Option<IndexingGuard> {
+        let mut indexing = self.indexing.lock().unwrap_or_else(PoisonError::into_inner);
+        if !indexing.insert(index_dir.to_path_buf()) {
+            return None;
+        }
+        Some(IndexingGuard {
+            index_dir: index_dir.to_path_buf(),
+            indexing: self.indexing.clone(),
+        })
+    }
+
+    pub fn is_indexing(&self, index_dir: &Path) -> bool {
+        self.indexing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(index_dir)
+    }
+
+    // 重建 guard 对应目录中的索引，完成后新的搜索使用新索引
+    pub fn index_directory(
+        &self,
+        guard: &IndexingGuard,
+        path: &Path,
+        analyzer_options: &AnalyzerOptions,
+        observer: &dyn IndexObserver,
+    ) -> Result<Searcher> {
+        let index_dir = guard.index_dir();
+        let indexer = Indexer::new(index_dir, analyzer_options.clone());
+        let searcher = indexer.index_directory(path, observer)?;
+        Ok(self.insert(index_dir, analyzer_options, searcher))
+    }
+
+    // 关闭索引，已经取出的句柄仍可继续使用
+    pub fn close(&self, index_dir: &Path) {
+        self.write().remove(index_dir);
+    }
+
+    pub fn open_indexes(&self) -> Vec<PathBuf> {
+        self.read().keys().cloned().collect()
+    }
+
+    fn insert(
+        &self,
+        index_dir: &Path,
+        analyzer_options: &AnalyzerOptions,
+        searcher: Searcher,
+    ) -> Searcher {
+        let index = OpenIndex {
+            analyzer_options: analyzer_options.clone(),
+            searcher: searcher.clone(),
+        };
+        self.write().insert(index_dir.to_path_buf(), index);
+        searcher
+    }
+
+    // 锁内只有句柄的整体替换，持锁线程 panic 也不会留下不一致的状态
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<PathBuf, OpenIndex>> {
+        self.indexes.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<PathBuf, OpenIndex>> {
+        self.indexes.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
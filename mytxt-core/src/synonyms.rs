@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use tantivy::query::{BooleanQuery, Occur, PhraseQuery, Query, QueryParser, TermQuery};
+use tantivy::Term;
+
+// 同义词表：每行一组等价词，词之间用空格或逗号分隔，例如 "发票 票据"
+#[derive(Clone, Debug, Default)]
+pub struct SynonymTable {
+    groups: Vec<Vec<String>>,
+}
+
+impl SynonymTable {
+    pub fn parse(text: &str) -> Self {
+        let groups = text
+            .lines()
+            .map(|line| {
+                line.split(|c: char| c.is_whitespace() || c == ',' || c == '，')
+                    .map(|w| w.trim_matches('"'))
+                    .filter(|w| !w.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| group.len() > 1)
+            .collect();
+        Self { groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // Rewrite every term or phrase of `query` that matches a synonym into a
+    // should-clause over the whole group. Synonyms go through the same parser,
+    // so they are analyzed exactly like the user's own terms.
+    pub fn expand(&self, query: Box<dyn Query>, query_parser: &QueryParser) -> Box<dyn Query> {
+        if self.is_empty() {
+            return query;
+        }
+        let expansions = self.build_expansions(query_parser);
+        rewrite(query.as_ref(), &expansions)
+    }
+
+    // Maps the analyzed terms of every synonym to the index of its group's queries.
+    fn build_expansions(&self, query_parser: &QueryParser) -> Expansions {
+        let mut expansions = Expansions::default();
+        for group in &self.groups {
+            let queries: Vec<Box<dyn Query>> = group
+                .iter()
+                .filter_map(|word| query_parser.parse_query(&format!("\"{}\"", word)).ok())
+                .collect();
+            let group_index = expansions.groups.len();
+            for query in &queries {
+                let terms = leaf_terms(query.as_ref());
+                if !terms.is_empty() {
+                    expansions.by_terms.insert(terms, group_index);
+                }
+            }
+            expansions.groups.push(queries);
+        }
+        expansions
+    }
+}
+
+#[derive(Default)]
+struct Expansions {
+    by_terms: HashMap<Vec<Term>, usize>,
+    groups: Vec<Vec<Box<dyn Query>>>,
+}
+
+fn leaf_terms(query: &dyn Query) -> Vec<Term> {
+    let mut terms = Vec::new();
+    if query.is::<TermQuery>() || query.is::<PhraseQuery>() {
+        query.query_terms(&mut |term, _| terms.push(term.clone()));
+    }
+    terms
+}
+
+fn rewrite(query: &dyn Query, expansions: &Expansions) -> Box<dyn Query> {
+    if let Some(boolean) = query.downcast_ref::<BooleanQuery>() {
+        let clauses = boolean
+            .clauses()
+            .iter()
+            .map(|(occur, sub)| (*occur, rewrite(sub.as_ref(), expansions)))
+            .collect();
+        return Box::new(BooleanQuery::new(clauses));
+    }
+
+    let terms = leaf_terms(query);
+    match expansions.by_terms.get(&terms) {
+        Some(&group_index) => {
+            let mut clauses = vec![(Occur::Should, query.box_clone())];
+            for synonym in &expansions.groups[group_index] {
+                if leaf_terms(synonym.as_ref()) != terms {
+                    clauses.push((Occur::Should, synonym.box_clone()));
+                }
+            }
+            Box::new(BooleanQuery::new(clauses))
+        }
+        None => query.box_clone(),
+    }
+}
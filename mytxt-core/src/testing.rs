@@ -0,0 +1,81 @@
+// 测试共用的辅助函数：默认的分词和搜索参数，以及在临时目录中建立的索引
+use crate::analyzer::{AnalyzerKind, AnalyzerOptions};
+use crate::{Indexer, SearchOptions, Searcher};
+use docx_rust::document::Paragraph;
+use docx_rust::Docx;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+// 与界面默认设置一致：jieba 分词 + 规范化，无用户词典和停用词
+pub fn analyzer_options() -> AnalyzerOptions {
+    AnalyzerOptions {
+        kind: AnalyzerKind::JiebaNormalized,
+        user_dict: String::new(),
+        stopwords: String::new(),
+    }
+}
+
+pub fn search_options() -> SearchOptions {
+    SearchOptions {
+        synonyms: None,
+        lenient: false,
+        limit: 10,
+        snippet_length: 100,
+        snippets_per_result: 2,
+        filters: Vec::new(),
+    }
+}
+
+// 每段正文一个段落
+pub fn write_docx(path: &Path, paragraphs: &[&str]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut docx = Docx::default();
+    for text in paragraphs {
+        docx.document.push(Paragraph::default().push_text(*text));
+    }
+    docx.write_file(path).unwrap();
+}
+
+// 临时目录，文档在 docs 下，索引在 index 下；离开作用域时整个删除
+pub struct TempIndex {
+    dir: TempDir,
+}
+
+impl TempIndex {
+    // files 为相对 docs 目录的路径和每段正文，只写入文件，不建立索引
+    pub fn new(files: &[(&str, &[&str])]) -> Self {
+        let index = Self {
+            dir: tempfile::tempdir().unwrap(),
+        };
+        for (name, paragraphs) in files {
+            index.write_docx(&format!("docs/{}", name), paragraphs);
+        }
+        index
+    }
+
+    // 相对临时目录的路径
+    pub fn path(&self, relative: &str) -> PathBuf {
+        self.dir.path().join(relative)
+    }
+
+    pub fn docs(&self) -> PathBuf {
+        self.path("docs")
+    }
+
+    pub fn index_dir(&self) -> PathBuf {
+        self.path("index")
+    }
+
+    pub fn write_docx(&self, relative: &str, paragraphs: &[&str]) {
+        write_docx(&self.path(relative), paragraphs);
+    }
+
+    pub fn indexer(&self) -> Indexer {
+        Indexer::new(self.index_dir(), analyzer_options())
+    }
+
+    // 为 docs 目录建立索引
+    pub fn build(&self) -> Searcher {
+        self.indexer().index_directory(&self.docs(), &()).unwrap()
+    }
+}
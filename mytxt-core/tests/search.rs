@@ -0,0 +1,222 @@
+// 在临时目录中建立索引并通过 Searcher::search 搜索，检查返回的 SearchResponse
+use mytxt_core::facets::{FacetField, FacetFilter};
+use mytxt_core::highlight::HighlightedText;
+use mytxt_core::testing::{search_options as options, TempIndex};
+use mytxt_core::{
+    SearchError, SearchObserver, SearchOptions, SearchRequest, SearchResponse, Searcher,
+};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+struct Fixture {
+    index: TempIndex,
+    searcher: Searcher,
+}
+
+impl Fixture {
+    // files 为相对 docs 目录的路径和每段正文
+    fn new(files: &[(&str, &[&str])]) -> Self {
+        let index = TempIndex::new(files);
+        let searcher = index.build();
+        Self { index, searcher }
+    }
+
+    fn search(&self, query: &str, options: SearchOptions) -> Result<SearchResponse, SearchError> {
+        let request = SearchRequest::new(query, options);
+        self.searcher
+            .search(&request, &(), Arc::new(AtomicBool::new(false)))
+    }
+
+    // 命中的文件名，按相关度排列
+    fn names(&self, query: &str) -> Vec<String> {
+        names(&self.search(query, options()).unwrap())
+    }
+}
+
+fn names(response: &SearchResponse) -> Vec<String> {
+    response
+        .results
+        .iter()
+        .map(|r| {
+            Path::new(&r.path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
+
+#[test]
+fn returns_ranked_results_with_snippets_and_metadata() {
+    let fixture = Fixture::new(&[
+        (
+            "报告.docx",
+            &["季度报告", "销售额继续增长，增长主要来自华东"],
+        ),
+        ("计划.docx", &["明年的计划", "销售目标"]),
+        ("杂记.docx", &["今天天气不错"]),
+    ]);
+    let response = fixture.search("增长", options()).unwrap();
+    assert_eq!(names(&response), ["报告.docx"]);
+
+    let result = &response.results[0];
+    assert_eq!(result.extension, "docx");
+    assert_eq!(result.root, fixture.index.docs().to_str().unwrap());
+    assert!(result.modified.is_some());
+    let snippet = &result.snippets[0];
+    let highlighted: Vec<&str> = snippet
+        .highlights
+        .iter()
+        .map(|range| &snippet.text[range.clone()])
+        .collect();
+    assert_eq!(highlighted, ["增长", "增长"]);
+
+    let both = fixture.search("销售", options()).unwrap();
+    assert_eq!(both.results.len(), 2);
+    assert!(both.ignored.is_empty());
+}
+
+#[test]
+fn respects_boolean_operators_and_limit() {
+    let fixture = Fixture::new(&[
+        ("a.docx", &["苹果 香蕉"]),
+        ("b.docx", &["苹果"]),
+        ("c.docx", &["香蕉"]),
+    ]);
+    assert_eq!(fixture.names("苹果 AND 香蕉"), ["a.docx"]);
+    assert_eq!(fixture.names("苹果 -香蕉"), ["b.docx"]);
+
+    let mut limited = options();
+    limited.limit = 1;
+    let response = fixture.search("苹果 OR 香蕉", limited).unwrap();
+    assert_eq!(response.results.len(), 1);
+    // 分布统计覆盖全部命中，不受结果数上限影响
+    let file_types = response
+        .facets
+        .iter()
+        .find(|f| f.field == FacetField::FileType)
+        .unwrap();
+    assert_eq!(file_types.counts[0].count, 3);
+}
+
+#[test]
+fn normalized_analyzer_matches_traditional_and_full_width_text() {
+    let fixture = Fixture::new(&[
+        ("繁体.docx", &["軟體測試"]),
+        ("全角.docx", &["ＡＢＣ１２３"]),
+    ]);
+    assert_eq!(fixture.names("测试"), ["繁体.docx"]);
+    assert_eq!(fixture.names("abc123"), ["全角.docx"]);
+}
+
+#[test]
+fn facet_filters_narrow_results() {
+    let fixture = Fixture::new(&[
+        ("项目/2024/a.docx", &["预算"]),
+        ("项目/2023/b.docx", &["预算"]),
+        ("其他/c.docx", &["预算"]),
+    ]);
+    let mut filtered = options();
+    filtered.filters = vec![FacetFilter::parse("folder", "项目").unwrap()];
+    let mut found = names(&fixture.search("预算", filtered).unwrap());
+    found.sort();
+    assert_eq!(found, ["a.docx", "b.docx"]);
+
+    let mut filtered = options();
+    filtered.filters = vec![FacetFilter::parse("folder", "项目/2024").unwrap()];
+    assert_eq!(
+        names(&fixture.search("预算", filtered).unwrap()),
+        ["a.docx"]
+    );
+}
+
+#[test]
+fn text_ranges_are_searchable() {
+    let fixture = Fixture::new(&[("a.docx", &["apple"]), ("z.docx", &["zebra"])]);
+    assert_eq!(fixture.names("content:[a TO b]"), ["a.docx"]);
+    let mut both = fixture.names("content:[a TO z} OR zebra");
+    both.sort();
+    assert_eq!(both, ["a.docx", "z.docx"]);
+}
+
+#[test]
+fn syntax_errors_fail_strictly_and_are_skipped_leniently() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"])]);
+    match fixture.search("苹果 AND (", options()) {
+        Err(SearchError::Query(issues)) => assert!(!issues.is_empty()),
+        other => panic!("expected a query error, got {:?}", other.map(|r| r.results)),
+    }
+
+    let mut lenient = options();
+    lenient.lenient = true;
+    let response = fixture.search("苹果 AND (", lenient).unwrap();
+    assert_eq!(names(&response), ["a.docx"]);
+    assert!(!response.ignored.is_empty());
+}
+
+// 记录流式发送的摘要对应的结果下标
+#[derive(Default)]
+struct SnippetRecorder(Mutex<Vec<usize>>);
+
+impl SearchObserver for SnippetRecorder {
+    fn snippets(&self, snippets: &[(usize, Vec<HighlightedText>)]) {
+        let mut indices = self.0.lock().unwrap();
+        indices.extend(snippets.iter().map(|(i, _)| *i));
+    }
+}
+
+#[test]
+fn observer_receives_snippets_for_every_result() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"]), ("b.docx", &["苹果 香蕉"])]);
+    let request = SearchRequest::new("苹果", options());
+    let recorder = SnippetRecorder::default();
+    let response = fixture
+        .searcher
+        .search(&request, &recorder, Arc::new(AtomicBool::new(false)))
+        .unwrap();
+    let mut indices = recorder.0.into_inner().unwrap();
+    indices.sort();
+    assert_eq!(indices, (0..response.results.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn cancelled_search_reports_cancelled() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"])]);
+    let request = SearchRequest::new("苹果", options());
+    let result = fixture
+        .searcher
+        .search(&request, &(), Arc::new(AtomicBool::new(true)));
+    assert!(matches!(result, Err(SearchError::Cancelled)));
+}
+
+#[test]
+fn update_files_replaces_changed_and_drops_removed_documents() {
+    let fixture = Fixture::new(&[("a.docx", &["旧内容"]), ("b.docx", &["将被删除"])]);
+    let docs = fixture.index.docs();
+    fixture.index.write_docx("docs/a.docx", &["新内容"]);
+    fixture.index.write_docx("docs/c.docx", &["新文件"]);
+    std::fs::remove_file(docs.join("b.docx")).unwrap();
+
+    let searcher = fixture
+        .index
+        .indexer()
+        .update_files(
+            &docs,
+            &[docs.join("a.docx"), docs.join("c.docx")],
+            &[docs.join("b.docx")],
+            &(),
+        )
+        .unwrap();
+    assert_eq!(searcher.stats().unwrap().num_docs, 2);
+
+    let fixture = Fixture {
+        searcher,
+        ..fixture
+    };
+    assert!(fixture.names("旧内容").is_empty());
+    assert!(fixture.names("删除").is_empty());
+    assert_eq!(fixture.names("新内容"), ["a.docx"]);
+    assert_eq!(fixture.names("新文件"), ["c.docx"]);
+}
@@ -0,0 +1,32 @@
+use super::http::Response;
+
+// 内置的网页界面，编译进二进制，用浏览器打开 http://127.0.0.1:<端口>/ 即可使用
+const ASSETS: [(&str, &str, &str); 3] = [
+    (
+        "/",
+        "text/html; charset=utf-8",
+        include_str!("web/index.html"),
+    ),
+    (
+        "/app.js",
+        "text/javascript; charset=utf-8",
+        include_str!("web/app.js"),
+    ),
+    (
+        "/style.css",
+        "text/css; charset=utf-8",
+        include_str!("web/style.css"),
+    ),
+];
+
+pub fn find(path: &str) -> Option<Response> {
+    let path = if path == "/index.html" { "/" } else { path };
+    ASSETS
+        .iter()
+        .find(|(p, _, _)| *p == path)
+        .map(|&(_, content_type, body)| Response {
+            status: 200,
+            content_type,
+            body: body.as_bytes().to_vec(),
+        })
+}
@@ -0,0 +1,360 @@
+use super::http::{Request, Response};
+use super::ApiState;
+use crate::message::{AppMessage, IndexMessage};
+use crate::search::diagnostics::QueryIssue;
+use crate::search::facets::{self, FacetFilter, FacetSummary};
+use crate::search::highlight::HighlightedText;
+use crate::search::query::{SearchError, SearchRequest, SearchResult};
+use crate::utils::file_utils;
+use mytxt_core::Searcher;
+use serde::Serialize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use walkdir::WalkDir;
+
+// 单次搜索最多返回的结果数
+const MAX_LIMIT: usize = 1000;
+
+// 返回给调用方的区间都是字符（Unicode 标量值）下标而不是字节下标
+#[derive(Serialize)]
+struct ApiSnippet {
+    text: String,
+    highlights: Vec<[usize; 2]>,
+}
+
+#[derive(Serialize)]
+struct ApiResult {
+    path: String,
+    score: f32,
+    root: String,
+    extension: String,
+    // Unix 时间戳（秒）
+    modified: Option<i64>,
+    snippets: Vec<ApiSnippet>,
+}
+
+#[derive(Serialize)]
+struct ApiFacetCount {
+    // 可直接用作 filter 参数的值，如 项目/2024
+    value: String,
+    label: String,
+    count: u64,
+}
+
+#[derive(Serialize)]
+struct ApiFacet {
+    field: &'static str,
+    label: &'static str,
+    counts: Vec<ApiFacetCount>,
+}
+
+#[derive(Serialize)]
+struct ApiIssue {
+    message: String,
+    span: Option<[usize; 2]>,
+}
+
+#[derive(Serialize)]
+struct SearchBody {
+    query: String,
+    total: usize,
+    duration_ms: u128,
+    results: Vec<ApiResult>,
+    facets: Vec<ApiFacet>,
+    // 宽松模式下被忽略的部分
+    ignored: Vec<ApiIssue>,
+}
+
+#[derive(Serialize)]
+struct QueryErrorBody {
+    error: String,
+    issues: Vec<ApiIssue>,
+}
+
+#[derive(Serialize)]
+struct DocumentBody {
+    path: String,
+    content: String,
+    // 带 q 参数时为命中词在 content 中的位置
+    matches: Vec<[usize; 2]>,
+}
+
+#[derive(Serialize)]
+struct StatsBody {
+    index_dir: String,
+    num_docs: u64,
+    segments: usize,
+    size_bytes: u64,
+    roots: Vec<String>,
+    facets: Vec<ApiFacet>,
+}
+
+#[derive(Serialize)]
+struct ReindexBody {
+    status: &'static str,
+    root: String,
+}
+
+// GET /search?q=...&limit=20&filter=folder=项目
+pub fn search(state: &ApiState, request: &Request) -> Response {
+    let Some(query) = request.param("q").filter(|q| !q.trim().is_empty()) else {
+        return Response::error(400, "缺少查询参数 q");
+    };
+    let mut options = state.config().search_options;
+    if let Some(limit) = request.param("limit") {
+        match limit.parse::<usize>() {
+            Ok(limit) => options.limit = limit.clamp(1, MAX_LIMIT),
+            Err(_) => return Response::error(400, "limit 应为正整数"),
+        }
+    }
+    for filter in request.params("filter") {
+        let parsed = filter
+            .split_once('=')
+            .and_then(|(field, value)| FacetFilter::parse(field, value));
+        match parsed {
+            Some(filter) => options.filters.push(filter),
+            None => {
+                return Response::error(
+                    400,
+                    format!(
+                        "无法识别的筛选条件: {}（应为 folder=、file_type= 或 year=）",
+                        filter
+                    ),
+                )
+            }
+        }
+    }
+
+    let searcher = match open(state) {
+        Ok(searcher) => searcher,
+        Err(response) => return response,
+    };
+    let search_request = SearchRequest::new(query, options);
+    match searcher.search(&search_request, &(), Arc::default()) {
+        Ok(response) => Response::json(
+            200,
+            &SearchBody {
+                query: search_request.query.clone(),
+                total: response.results.len(),
+                duration_ms: response.duration.as_millis(),
+                results: response.results.iter().map(api_result).collect(),
+                facets: response.facets.iter().map(api_facet).collect(),
+                ignored: api_issues(query, &response.ignored),
+            },
+        ),
+        Err(SearchError::Query(issues)) => Response::json(
+            400,
+            &QueryErrorBody {
+                error: "查询语法错误".to_string(),
+                issues: api_issues(query, &issues),
+            },
+        ),
+        Err(e) => Response::error(500, e.to_string()),
+    }
+}
+
+// GET /document?path=...&q=...，只允许读取索引中的 docx 文件
+pub fn document(state: &ApiState, request: &Request) -> Response {
+    let Some(path) = request.param("path") else {
+        return Response::error(400, "缺少参数 path");
+    };
+    let searcher = match open(state) {
+        Ok(searcher) => searcher,
+        Err(response) => return response,
+    };
+    match is_indexed(&searcher, Path::new(path)) {
+        Ok(true) => {}
+        Ok(false) => return Response::error(403, "只能读取已索引的文件"),
+        Err(e) => return Response::error(500, e.to_string()),
+    }
+
+    let content = match file_utils::read_file_content(Path::new(path)) {
+        Ok(content) => content,
+        Err(e) => return Response::error(404, format!("无法读取文件: {}", e)),
+    };
+    let matches = match request.param("q").filter(|q| !q.trim().is_empty()) {
+        Some(query) => {
+            match searcher.match_ranges(&content, query, &state.config().search_options) {
+                Ok(ranges) => char_ranges(&content, &ranges),
+                Err(e) => return Response::error(500, e.to_string()),
+            }
+        }
+        None => Vec::new(),
+    };
+    Response::json(
+        200,
+        &DocumentBody {
+            path: path.to_string(),
+            content,
+            matches,
+        },
+    )
+}
+
+// GET /stats
+pub fn stats(state: &ApiState) -> Response {
+    let searcher = match open(state) {
+        Ok(searcher) => searcher,
+        Err(response) => return response,
+    };
+    let (stats, roots) = match searcher.stats().and_then(|s| Ok((s, searcher.roots()?))) {
+        Ok(result) => result,
+        Err(e) => return Response::error(500, e.to_string()),
+    };
+    let size_bytes = WalkDir::new(&state.index_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum();
+    Response::json(
+        200,
+        &StatsBody {
+            index_dir: state.index_dir.display().to_string(),
+            num_docs: stats.num_docs,
+            segments: stats.segments,
+            size_bytes,
+            roots,
+            facets: stats.facets.iter().map(api_facet).collect(),
+        },
+    )
+}
+
+// POST /reindex：在后台按原来的根目录重建索引，立即返回
+pub fn reindex(state: &Arc<ApiState>) -> Response {
+    let searcher = match open(state) {
+        Ok(searcher) => searcher,
+        Err(response) => return response,
+    };
+    let mut roots = match searcher.roots() {
+        Ok(roots) => roots,
+        Err(e) => return Response::error(500, e.to_string()),
+    };
+    // 重建会清空整个索引，只支持单个根目录，多个时拒绝而不是丢掉其余的
+    let root = match roots.len() {
+        0 => return Response::error(409, "索引中没有记录根目录，请先建立索引"),
+        1 => PathBuf::from(roots.remove(0)),
+        _ => return Response::error(409, "索引中有多个根目录，请在界面中重建索引"),
+    };
+    // 与界面共用占用标记，界面正在重建时同样返回 409
+    let Some(indexing) = state.service.begin_indexing(&state.index_dir) else {
+        return Response::error(409, "正在重建索引");
+    };
+
+    let body = ReindexBody {
+        status: "started",
+        root: root.display().to_string(),
+    };
+    let state = state.clone();
+    thread::spawn(move || {
+        let analyzer_options = state.config().analyzer_options;
+        let result = state
+            .service
+            .index_directory(&indexing, &root, &analyzer_options, &());
+        drop(indexing);
+        // 界面中运行时只通知结果，不发送进度，界面上正在进行的搜索不受影响
+        let message = match result {
+            Ok(_) => IndexMessage::Finished,
+            Err(e) => {
+                eprintln!("Reindex error: {}", e);
+                IndexMessage::Error(e.to_string())
+            }
+        };
+        if let Some(sender) = &state.sender {
+            let _ = sender.send(AppMessage::Index(message));
+        }
+    });
+    Response::json(202, &body)
+}
+
+fn open(state: &ApiState) -> Result<Searcher, Response> {
+    state
+        .service
+        .open(&state.index_dir, &state.config().analyzer_options)
+        .map_err(|e| Response::error(503, e.to_string()))
+}
+
+// 路径必须与索引中记录的某个 docx 文件完全一致；
+// 再比较规范化后的路径，防止索引之后被换成指向根目录以外的符号链接
+fn is_indexed(searcher: &Searcher, path: &Path) -> anyhow::Result<bool> {
+    if !searcher.contains_file(path)? {
+        return Ok(false);
+    }
+    let Ok(path) = path.canonicalize() else {
+        return Ok(false);
+    };
+    Ok(searcher
+        .roots()?
+        .iter()
+        .filter_map(|root| Path::new(root).canonicalize().ok())
+        .any(|root| path.starts_with(root)))
+}
+
+fn api_result(result: &SearchResult) -> ApiResult {
+    ApiResult {
+        path: result.path.clone(),
+        score: result.score,
+        root: result.root.clone(),
+        extension: result.extension.clone(),
+        modified: result.modified.map(|m| m.into_timestamp_secs()),
+        snippets: result.snippets.iter().map(api_snippet).collect(),
+    }
+}
+
+fn api_snippet(snippet: &HighlightedText) -> ApiSnippet {
+    ApiSnippet {
+        highlights: char_ranges(&snippet.text, &snippet.highlights),
+        text: snippet.text.clone(),
+    }
+}
+
+fn api_facet(summary: &FacetSummary) -> ApiFacet {
+    ApiFacet {
+        field: summary.field.field_name(),
+        label: summary.field.label(),
+        counts: summary
+            .counts
+            .iter()
+            .map(|c| ApiFacetCount {
+                value: facets::facet_path(&c.facet),
+                label: facets::facet_label(&c.facet),
+                count: c.count,
+            })
+            .collect(),
+    }
+}
+
+fn api_issues(query: &str, issues: &[QueryIssue]) -> Vec<ApiIssue> {
+    issues
+        .iter()
+        .map(|issue| ApiIssue {
+            message: issue.message.clone(),
+            span: issue
+                .span
+                .as_ref()
+                .and_then(|span| char_ranges(query, std::slice::from_ref(span)).pop()),
+        })
+        .collect()
+}
+
+// 字节区间 -> 字符区间。区间按顺序且互不重叠，从上一个位置接着数，整段文本只遍历一遍
+fn char_ranges(text: &str, ranges: &[Range<usize>]) -> Vec<[usize; 2]> {
+    let mut byte = 0;
+    let mut chars = 0;
+    let mut char_index = |target: usize| {
+        if target < byte {
+            byte = 0;
+            chars = 0;
+        }
+        chars += text.get(byte..target).map_or(0, |s| s.chars().count());
+        byte = target;
+        chars
+    };
+    ranges
+        .iter()
+        .map(|r| [char_index(r.start), char_index(r.end)])
+        .collect()
+}
@@ -0,0 +1,156 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::io::{BufRead, Read, Write};
+
+// 请求行和请求头的总长度上限，以及请求体的长度上限
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_BODY: usize = 1024 * 1024;
+
+// 只实现本地 API 用得到的 HTTP/1.1 子集：每个连接一个请求，响应后关闭
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    // 已解码的查询参数，按出现顺序保存，可重复
+    pub query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn read(reader: &mut impl BufRead) -> Result<Self> {
+        let mut head = Vec::new();
+        let mut line = String::new();
+        // 限制读取的字节数，没有换行的超长一行也不会被整行读进内存
+        let mut head_reader = reader.take(MAX_HEAD as u64);
+        loop {
+            line.clear();
+            let n = head_reader.read_line(&mut line)?;
+            if n == 0 {
+                if head_reader.limit() == 0 {
+                    bail!("请求头过长");
+                }
+                bail!("连接在请求头结束前关闭");
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                break;
+            }
+            head.push(line.to_string());
+        }
+
+        let mut head = head.into_iter();
+        let request_line = head.next().context("缺少请求行")?;
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target), Some(_version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            bail!("无法解析的请求行: {}", request_line);
+        };
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let headers: Vec<(String, String)> = head
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+
+        let mut request = Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect(),
+            headers,
+            body: Vec::new(),
+        };
+        if let Some(length) = request.header("content-length") {
+            let length: usize = length.parse().context("无效的 Content-Length")?;
+            if length > MAX_BODY {
+                bail!("请求体过长");
+            }
+            request.body.resize(length, 0);
+            reader.read_exact(&mut request.body)?;
+        }
+        Ok(request)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn params<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.query
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(status: u16, value: &impl Serialize) -> Self {
+        Self {
+            status,
+            content_type: "application/json; charset=utf-8",
+            body: serde_json::to_vec(value).unwrap_or_default(),
+        }
+    }
+
+    // 错误统一为 {"error": "..."}
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        #[derive(Serialize)]
+        struct ErrorBody {
+            error: String,
+        }
+        Self::json(
+            status,
+            &ErrorBody {
+                error: message.into(),
+            },
+        )
+    }
+
+    // 页面只能加载本服务自己的脚本和样式，也不能被其他网页嵌入
+    pub fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nX-Content-Type-Options: nosniff\r\nContent-Security-Policy: default-src 'self'; frame-ancestors 'none'\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        )?;
+        out.write_all(&self.body)?;
+        out.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,257 @@
+mod assets;
+pub mod handlers;
+pub mod http;
+#[cfg(test)]
+mod tests;
+
+use self::http::{Request, Response};
+use crate::message::AppMessage;
+use crate::search::analyzer::AnalyzerOptions;
+use crate::search::query::SearchOptions;
+use anyhow::Result;
+use crossbeam_channel::Sender;
+use mytxt_core::IndexService;
+use std::io::BufReader;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+// 在接受连接的线程上回复 503，不能让慢速客户端长时间占住
+const BUSY_TIMEOUT: Duration = Duration::from_millis(100);
+// 同时处理的连接数上限
+const MAX_CONNECTIONS: usize = 32;
+
+// 从设置中取出的参数快照；端口和 token 变化时重新启动服务，其余随时更新
+#[derive(Clone)]
+pub struct ApiConfig {
+    // 0 表示由系统分配
+    pub port: u16,
+    // 为空时不校验
+    pub token: String,
+    pub search_options: SearchOptions,
+    pub analyzer_options: AnalyzerOptions,
+}
+
+// 各个连接共用的状态
+pub struct ApiState {
+    config: RwLock<ApiConfig>,
+    pub service: Arc<IndexService>,
+    pub index_dir: PathBuf,
+    // 图形界面中运行时把重建索引的结果发给界面，命令行中为 None
+    pub sender: Option<Sender<AppMessage>>,
+    // 正在处理的连接数
+    connections: AtomicUsize,
+    addr: SocketAddr,
+}
+
+impl ApiState {
+    pub fn config(&self) -> ApiConfig {
+        self.config
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+// 只监听 127.0.0.1 的 HTTP/JSON 接口，与界面使用同一个 IndexService
+pub struct ApiServer {
+    addr: SocketAddr,
+    state: Arc<ApiState>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ApiServer {
+    pub fn start(
+        config: ApiConfig,
+        service: Arc<IndexService>,
+        index_dir: &Path,
+        sender: Option<Sender<AppMessage>>,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.port))?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let state = Arc::new(ApiState {
+            config: RwLock::new(config),
+            service,
+            index_dir: index_dir.to_path_buf(),
+            sender,
+            connections: AtomicUsize::new(0),
+            addr,
+        });
+
+        let stop_flag = stop.clone();
+        let server_state = state.clone();
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop_flag.load(Ordering::SeqCst) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => {
+                        // 搜索可能较慢，每个连接一个线程；连接过多时直接拒绝
+                        let Some(connection) = Connection::begin(&state) else {
+                            reject_busy(stream);
+                            continue;
+                        };
+                        thread::spawn(move || handle_connection(connection, stream));
+                    }
+                    Err(e) => eprintln!("API accept error: {}", e),
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state: server_state,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    // 搜索、分词设置变化后更新，下一个请求开始生效
+    pub fn set_config(&self, config: ApiConfig) {
+        *self
+            .state
+            .config
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = config;
+    }
+
+    // 阻塞直到服务停止，命令行的 serve 用它一直运行
+    pub fn wait(mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+
+    pub fn stop(&mut self) {
+        let Some(handle) = self.handle.take() else {
+            return;
+        };
+        self.stop.store(true, Ordering::SeqCst);
+        // accept 是阻塞的，连一下自己让监听线程醒来看到停止标志
+        let _ = TcpStream::connect(self.addr);
+        let _ = handle.join();
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// 占用一个连接名额，处理完（线程结束）时释放
+struct Connection(Arc<ApiState>);
+
+impl Connection {
+    fn begin(state: &Arc<ApiState>) -> Option<Self> {
+        let previous = state.connections.fetch_add(1, Ordering::SeqCst);
+        // 先构造再判断，超出上限时 drop 会把计数减回去
+        let connection = Self(state.clone());
+        (previous < MAX_CONNECTIONS).then_some(connection)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn reject_busy(mut stream: TcpStream) {
+    let _ = stream.set_write_timeout(Some(BUSY_TIMEOUT));
+    let _ = Response::error(503, "连接过多，请稍后再试").write_to(&mut stream);
+}
+
+fn handle_connection(connection: Connection, stream: TcpStream) {
+    let state = &connection.0;
+    let _ = stream.set_read_timeout(Some(IO_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("API connection error: {}", e);
+            return;
+        }
+    };
+    let response = match Request::read(&mut BufReader::new(stream)) {
+        Ok(request) => route(state, &request),
+        Err(e) => Response::error(400, e.to_string()),
+    };
+    if let Err(e) = response.write_to(&mut writer) {
+        eprintln!("API write error: {}", e);
+    }
+}
+
+fn route(state: &Arc<ApiState>, request: &Request) -> Response {
+    if let Some(response) = reject_origin(state, request) {
+        return response;
+    }
+    // 页面本身不含数据，不校验 token，由页面在之后的请求里带上
+    if request.method == "GET" {
+        if let Some(response) = assets::find(&request.path) {
+            return response;
+        }
+    }
+    if let Some(response) = reject_token(state, request) {
+        return response;
+    }
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/search") => handlers::search(state, request),
+        ("GET", "/document") => handlers::document(state, request),
+        ("GET", "/stats") => handlers::stats(state),
+        ("POST", "/reindex") => handlers::reindex(state),
+        (_, "/search" | "/document" | "/stats" | "/reindex") => {
+            Response::error(405, format!("不支持的请求方法: {}", request.method))
+        }
+        _ => Response::error(404, format!("未知的接口: {}", request.path)),
+    }
+}
+
+// 只接受本机地址发来的请求：Host 必须是本机（防止 DNS 重绑定），
+// 浏览器带的 Origin 也必须是本服务自己（防止其他网页跨站调用）
+fn reject_origin(state: &ApiState, request: &Request) -> Option<Response> {
+    let port = state.addr.port();
+    let local =
+        |host: &str| host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port);
+    if !request.header("host").is_some_and(local) {
+        return Some(Response::error(403, "只接受发往本机地址的请求"));
+    }
+    if let Some(origin) = request.header("origin") {
+        let host = origin.strip_prefix("http://").unwrap_or_default();
+        if !local(host) {
+            return Some(Response::error(403, "不接受跨站请求"));
+        }
+    }
+    None
+}
+
+// 设置了 token 时校验 Authorization: Bearer 头或 token 参数
+fn reject_token(state: &ApiState, request: &Request) -> Option<Response> {
+    let expected = state.config().token;
+    if expected.is_empty() {
+        return None;
+    }
+    let token = request
+        .header("authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .or_else(|| request.param("token"));
+    match token {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => None,
+        _ => Some(Response::error(401, "缺少或错误的 token")),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
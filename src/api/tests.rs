@@ -0,0 +1,227 @@
+// 在临时目录中建立索引，启动监听随机端口的 ApiServer，用原始的 HTTP 请求检查各个接口
+use super::{ApiConfig, ApiServer};
+use crate::config::AppSettings;
+use mytxt_core::testing::TempIndex;
+use mytxt_core::IndexService;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+struct Fixture {
+    index: TempIndex,
+    service: Arc<IndexService>,
+    server: ApiServer,
+}
+
+impl Fixture {
+    // docs 目录下建立索引的文件，token 为空时不校验
+    fn new(files: &[(&str, &[&str])], token: &str) -> Self {
+        let index = TempIndex::new(files);
+        index.build();
+
+        let config = ApiConfig {
+            port: 0,
+            token: token.to_string(),
+            ..AppSettings::default().api_config()
+        };
+        let service = Arc::new(IndexService::new());
+        let server = ApiServer::start(config, service.clone(), &index.index_dir(), None).unwrap();
+        Self {
+            index,
+            service,
+            server,
+        }
+    }
+
+    fn path(&self, relative: &str) -> PathBuf {
+        self.index.path(relative)
+    }
+
+    fn host(&self) -> String {
+        format!("127.0.0.1:{}", self.server.addr().port())
+    }
+
+    // 带上本机 Host 头的请求
+    fn get(&self, target: &str) -> (u16, Value) {
+        self.request("GET", target, &[("Host", &self.host())])
+    }
+
+    fn request(&self, method: &str, target: &str, headers: &[(&str, &str)]) -> (u16, Value) {
+        let mut stream = TcpStream::connect(self.server.addr()).unwrap();
+        let mut request = format!("{} {} HTTP/1.1\r\n", method, target);
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn document(&self, path: &Path) -> (u16, Value) {
+        self.get(&format!("/document?path={}&q=苹果", encode(path)))
+    }
+}
+
+fn encode(path: &Path) -> String {
+    form_urlencoded::byte_serialize(path.to_str().unwrap().as_bytes()).collect()
+}
+
+#[test]
+fn search_returns_results_and_facets() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果 香蕉"]), ("b.docx", &["香蕉"])], "");
+    let (status, body) = fixture.get("/search?q=%E8%8B%B9%E6%9E%9C");
+    assert_eq!(status, 200);
+    assert_eq!(body["total"], 1);
+    let path = fixture.path("docs/a.docx");
+    assert_eq!(body["results"][0]["path"], path.to_str().unwrap());
+    assert!(!body["results"][0]["snippets"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+    assert!(!body["facets"].as_array().unwrap().is_empty());
+
+    let (status, body) = fixture.get("/search?q=");
+    assert_eq!(status, 400);
+    assert!(body["error"].is_string());
+    let (status, body) = fixture.get("/search?q=a%20AND%20(");
+    assert_eq!(status, 400);
+    assert!(!body["issues"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn document_returns_content_and_matches() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果 香蕉"])], "");
+    let (status, body) = fixture.document(&fixture.path("docs/a.docx"));
+    assert_eq!(status, 200);
+    assert_eq!(body["content"].as_str().unwrap().trim_end(), "苹果 香蕉");
+    assert_eq!(body["matches"], serde_json::json!([[0, 2]]));
+}
+
+#[test]
+fn document_only_serves_indexed_files() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"])], "");
+    std::fs::write(fixture.path("docs/.env"), "SECRET=1").unwrap();
+    fixture.index.write_docx("docs/new.docx", &["苹果"]);
+    fixture.index.write_docx("outside/secret.docx", &["苹果"]);
+
+    for path in [
+        // 根目录下未索引的文件
+        fixture.path("docs/.env"),
+        fixture.path("docs/new.docx"),
+        // 根目录以外
+        fixture.path("outside/secret.docx"),
+        // 用 .. 绕到根目录以外，或绕回已索引的文件
+        fixture.path("docs/../outside/secret.docx"),
+        fixture.path("docs/../docs/a.docx"),
+        fixture.path("docs/missing.docx"),
+    ] {
+        let (status, _) = fixture.document(&path);
+        assert_eq!(status, 403, "{}", path.display());
+    }
+    let (status, _) = fixture.get("/document");
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn stats_describe_the_index() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"]), ("b.docx", &["香蕉"])], "");
+    let (status, body) = fixture.get("/stats");
+    assert_eq!(status, 200);
+    assert_eq!(body["num_docs"], 2);
+    assert_eq!(
+        body["roots"],
+        serde_json::json!([fixture.path("docs").to_str().unwrap()])
+    );
+    assert!(body["size_bytes"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn reindex_conflicts_with_a_running_rebuild() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"])], "");
+    let indexing = fixture
+        .service
+        .begin_indexing(&fixture.index.index_dir())
+        .unwrap();
+    let (status, _) = fixture.request("POST", "/reindex", &[("Host", &fixture.host())]);
+    assert_eq!(status, 409);
+    drop(indexing);
+}
+
+#[test]
+fn reindex_rejects_multiple_roots() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"])], "");
+    fixture.index.write_docx("other/b.docx", &["香蕉"]);
+    let other = fixture.path("other/b.docx");
+    fixture
+        .index
+        .indexer()
+        .update_files(&fixture.path("other"), &[other], &[], &())
+        .unwrap();
+    assert_eq!(fixture.get("/stats").1["roots"].as_array().unwrap().len(), 2);
+
+    let (status, _) = fixture.request("POST", "/reindex", &[("Host", &fixture.host())]);
+    assert_eq!(status, 409);
+    assert!(!fixture.service.is_indexing(&fixture.index.index_dir()));
+}
+
+#[test]
+fn token_is_required_when_configured() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"])], "secret");
+    let host = fixture.host();
+    assert_eq!(fixture.get("/stats").0, 401);
+    assert_eq!(fixture.get("/stats?token=wrong").0, 401);
+    assert_eq!(fixture.get("/stats?token=secret").0, 200);
+    let (status, _) = fixture.request(
+        "GET",
+        "/stats",
+        &[("Host", &host), ("Authorization", "Bearer secret")],
+    );
+    assert_eq!(status, 200);
+    let (status, _) = fixture.request(
+        "GET",
+        "/stats",
+        &[("Host", &host), ("Authorization", "Bearer wrong")],
+    );
+    assert_eq!(status, 401);
+}
+
+#[test]
+fn foreign_host_and_origin_are_rejected() {
+    let fixture = Fixture::new(&[("a.docx", &["苹果"])], "");
+    let host = fixture.host();
+    let port = fixture.server.addr().port();
+    assert_eq!(fixture.request("GET", "/stats", &[]).0, 403);
+    let (status, _) = fixture.request("GET", "/stats", &[("Host", &format!("evil.com:{}", port))]);
+    assert_eq!(status, 403);
+    let (status, _) = fixture.request(
+        "GET",
+        "/stats",
+        &[("Host", &host), ("Origin", "http://evil.com")],
+    );
+    assert_eq!(status, 403);
+    let (status, _) = fixture.request(
+        "GET",
+        "/stats",
+        &[
+            ("Host", &host),
+            ("Origin", &format!("http://localhost:{}", port)),
+        ],
+    );
+    assert_eq!(status, 200);
+}
+
+#[test]
+fn oversized_head_is_rejected_without_buffering_it() {
+    // 没有换行的超长请求行
+    let mut reader = std::io::Cursor::new(vec![b'a'; 1024 * 1024]);
+    let error = super::http::Request::read(&mut reader).err().unwrap();
+    assert_eq!(error.to_string(), "请求头过长");
+    assert!(reader.position() < 64 * 1024);
+}
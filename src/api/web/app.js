@@ -0,0 +1,251 @@
+"use strict";
+
+// 与界面中央面板对应的网页版：搜索框、结果与高亮摘要、结果分布筛选、预览。
+// 接口返回的区间都是字符下标，用 Array.from 按码点切分后再取。
+
+const $ = (id) => document.getElementById(id);
+
+const state = {
+  query: "",
+  // field -> { value, label }，同一维度只保留一个条件，与界面的结果分布侧栏一致
+  filters: new Map(),
+  results: [],
+  selected: null,
+  matches: [],
+  current: 0,
+  // 只显示最后一次请求的结果
+  generation: 0,
+};
+
+// 带 ?token= 打开页面时记下 token，并从地址栏去掉
+const params = new URLSearchParams(location.search);
+if (params.has("token")) {
+  sessionStorage.setItem("token", params.get("token"));
+  history.replaceState(null, "", location.pathname);
+}
+
+async function api(path, query, options = {}) {
+  const url = path + "?" + new URLSearchParams(query || []);
+  const headers = {};
+  const token = sessionStorage.getItem("token");
+  if (token) {
+    headers["Authorization"] = "Bearer " + token;
+  }
+  const response = await fetch(url, { ...options, headers });
+  const body = await response.json().catch(() => ({ error: response.statusText }));
+  if (response.status === 401) {
+    $("token-form").hidden = false;
+    $("token").focus();
+  }
+  return { ok: response.ok, status: response.status, body };
+}
+
+function el(tag, className, text) {
+  const node = document.createElement(tag);
+  if (className) node.className = className;
+  if (text !== undefined) node.textContent = text;
+  return node;
+}
+
+// 把 [[start, end], ...] 对应的部分包进 <mark>，其余作为纯文本
+function highlighted(text, ranges) {
+  const chars = Array.from(text);
+  const fragment = document.createDocumentFragment();
+  const marks = [];
+  let cursor = 0;
+  for (const [start, end] of ranges) {
+    if (start < cursor) continue;
+    fragment.append(chars.slice(cursor, start).join(""));
+    const mark = el("mark", "", chars.slice(start, end).join(""));
+    marks.push(mark);
+    fragment.append(mark);
+    cursor = end;
+  }
+  fragment.append(chars.slice(cursor).join(""));
+  return { fragment, marks };
+}
+
+function showMessages(items) {
+  const box = $("messages");
+  box.replaceChildren(...items.map(([cls, text]) => el("div", cls, text)));
+}
+
+function issueText(query, issue) {
+  if (!issue.span) return issue.message;
+  const chars = Array.from(query);
+  return issue.message + "：" + chars.slice(issue.span[0], issue.span[1]).join("");
+}
+
+async function search() {
+  const query = $("query").value.trim();
+  if (!query) return;
+  state.query = query;
+  const generation = ++state.generation;
+
+  const args = [["q", query], ["limit", $("limit").value || "100"]];
+  for (const [field, filter] of state.filters) {
+    args.push(["filter", field + "=" + filter.value]);
+  }
+  $("summary").textContent = "正在搜索...";
+  const { ok, body } = await api("/search", args);
+  if (generation !== state.generation) return;
+
+  if (!ok) {
+    $("summary").textContent = "";
+    const messages = [["error", body.error]];
+    for (const issue of body.issues || []) {
+      messages.push(["error", issueText(query, issue)]);
+    }
+    showMessages(messages);
+    state.results = [];
+    renderResults();
+    return;
+  }
+
+  $("summary").textContent = `共 ${body.total} 条 (${body.duration_ms} ms)`;
+  showMessages((body.ignored || []).map((issue) => ["warning", "已忽略 " + issueText(query, issue)]));
+  state.results = body.results;
+  renderFacets(body.facets);
+  renderResults();
+}
+
+function renderFacets(facets) {
+  $("facets").hidden = facets.length === 0 && state.filters.size === 0;
+
+  const active = $("active-filters");
+  active.replaceChildren();
+  for (const [field, filter] of state.filters) {
+    const chip = el("button", "chip", filter.label + " ✖");
+    chip.title = "移除此筛选";
+    chip.onclick = () => {
+      state.filters.delete(field);
+      search();
+    };
+    active.append(chip);
+  }
+  if (state.filters.size > 0) {
+    const clear = el("button", "chip", "清除全部筛选");
+    clear.onclick = () => {
+      state.filters.clear();
+      search();
+    };
+    active.append(clear);
+  }
+
+  const list = $("facet-list");
+  list.replaceChildren();
+  for (const facet of facets) {
+    const group = el("div", "facet-group");
+    group.append(el("h3", "", facet.label));
+    if (facet.counts.length === 0) {
+      group.append(el("div", "muted", "无"));
+    }
+    for (const count of facet.counts) {
+      const selected = state.filters.get(facet.field)?.value === count.value;
+      const button = el("button", selected ? "selected" : "", `${count.label} (${count.count})`);
+      button.title = count.value;
+      button.onclick = () => {
+        if (selected) return;
+        state.filters.set(facet.field, { value: count.value, label: `${facet.label}: ${count.label}` });
+        search();
+      };
+      group.append(button);
+    }
+    list.append(group);
+  }
+}
+
+function sorted(results) {
+  const byPath = (a, b) => a.path.localeCompare(b.path);
+  const modified = (r) => r.modified ?? 0;
+  switch ($("sort").value) {
+    case "newest":
+      return [...results].sort((a, b) => modified(b) - modified(a));
+    case "oldest":
+      return [...results].sort((a, b) => modified(a) - modified(b));
+    case "path":
+      return [...results].sort(byPath);
+    default:
+      return results;
+  }
+}
+
+function renderResults() {
+  const list = $("results");
+  list.replaceChildren();
+  if (state.results.length === 0) {
+    list.append(el("li", "muted", "无结果"));
+    return;
+  }
+  for (const result of sorted(state.results)) {
+    const item = el("li", result.path === state.selected ? "selected" : "");
+    item.append(el("div", "path", result.path));
+    const meta = [result.root, result.extension];
+    if (result.modified != null) {
+      meta.push(new Date(result.modified * 1000).toLocaleString());
+    }
+    item.append(el("div", "meta", meta.filter(Boolean).join(" · ")));
+    for (const snippet of result.snippets) {
+      const line = el("div", "snippet");
+      line.append(highlighted(snippet.text, snippet.highlights).fragment);
+      item.append(line);
+    }
+    item.onclick = () => {
+      for (const other of list.children) other.classList.remove("selected");
+      item.classList.add("selected");
+      preview(result.path);
+    };
+    list.append(item);
+  }
+}
+
+async function preview(path) {
+  state.selected = path;
+  $("preview-title").textContent = path;
+  $("preview").textContent = "正在加载...";
+  $("preview-nav").hidden = true;
+  const { ok, body } = await api("/document", [["path", path], ["q", state.query]]);
+  if (state.selected !== path) return;
+  if (!ok) {
+    $("preview").replaceChildren(el("span", "error", body.error));
+    return;
+  }
+  const { fragment, marks } = highlighted(body.content, body.matches);
+  $("preview").replaceChildren(fragment);
+  state.matches = marks;
+  state.current = 0;
+  $("preview-nav").hidden = marks.length === 0;
+  jump(0);
+}
+
+// 在预览中的匹配之间跳转，和预览窗口的 F3 / Shift+F3 一样循环
+function jump(delta) {
+  const count = state.matches.length;
+  if (count === 0) return;
+  state.matches[state.current].classList.remove("current");
+  state.current = (state.current + delta + count) % count;
+  const mark = state.matches[state.current];
+  mark.classList.add("current");
+  mark.scrollIntoView({ block: "center" });
+  $("match-count").textContent = `${state.current + 1} / ${count}`;
+}
+
+$("search-form").onsubmit = (event) => {
+  event.preventDefault();
+  search();
+};
+$("token-form").onsubmit = (event) => {
+  event.preventDefault();
+  sessionStorage.setItem("token", $("token").value);
+  $("token-form").hidden = true;
+  search();
+};
+$("sort").onchange = renderResults;
+$("prev-match").onclick = () => jump(-1);
+$("next-match").onclick = () => jump(1);
+document.addEventListener("keydown", (event) => {
+  if (event.key === "F3") {
+    event.preventDefault();
+    jump(event.shiftKey ? -1 : 1);
+  }
+});
@@ -1,13 +1,8 @@
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Default)]
 pub enum AppState {
+    #[default]
     Idle,
     Indexing { progress: f32 },
     Searching,
 }
-
-impl Default for AppState {
-    fn default() -> Self {
-        AppState::Idle
-    }
-}
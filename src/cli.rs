@@ -0,0 +1,401 @@
+use crate::api::ApiServer;
+use crate::config::{AnalyzerKind, AppSettings};
+use crate::search::analyzer::AnalyzerOptions;
+use crate::search::engine;
+use crate::search::facets::{self, FacetFilter};
+use crate::search::highlight::HighlightedText;
+use crate::search::query::{SearchRequest, SearchResult};
+use crate::utils::export::{self, ExportFormat};
+use anyhow::{anyhow, bail, Context, Result};
+use mytxt_core::{indexer, IndexObserver, IndexService, Indexer};
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+const USAGE: &str = "\
+用法:
+  mytxt                                  启动图形界面
+  mytxt index <目录>                     为目录建立索引
+  mytxt search <查询> [选项]             在已有索引中搜索
+      --limit <N>                        最多返回 N 条结果（默认 20）
+      --json                             以 JSON Lines 输出
+      --filter <字段>=<值>               按 folder、file_type、year 筛选，可重复
+  mytxt stats                            显示索引的统计信息
+  mytxt watch <目录> [--interval <秒>]   监视目录，只重新索引变化的文件（默认每 10 秒检查一次）
+                                         启动时先完整重建一次索引；更新期间其他地方无法同时写入索引
+  mytxt serve [选项]                     在 127.0.0.1 上提供 HTTP/JSON 搜索接口和网页版
+      --port <端口>                      监听端口（默认为设置中的端口，0 表示随机）
+      --token <token>                    要求请求带上该 token（默认为设置中的 token）
+
+通用选项:
+      --analyzer <jieba|normalized>      分词器，需与建立索引时一致（默认为设置中的分词器）
+
+分词器、用户词典、停用词、同义词等未在命令行指定的设置，使用图形界面中保存的设置；
+索引与图形界面共用，保存在应用数据目录中（mytxt stats 显示其位置）
+";
+
+// 作为命令行工具运行的子命令
+const COMMANDS: [&str; 6] = ["index", "search", "stats", "watch", "serve", "help"];
+
+// 需要带值的选项，其余以 -- 开头的都是开关
+const VALUE_OPTIONS: [&str; 6] = ["limit", "filter", "interval", "analyzer", "port", "token"];
+const DEFAULT_LIMIT: usize = 20;
+const DEFAULT_INTERVAL_SECS: u64 = 10;
+
+// 以已知子命令或 -h/--help 启动时才作为命令行工具运行；
+// 其他参数（如“打开方式”传入的文件路径）仍打开图形界面
+pub fn is_command(args: &[String]) -> bool {
+    match Args::parse(args) {
+        Ok(args) => {
+            args.flag("help")
+                || args
+                    .positional
+                    .first()
+                    .is_some_and(|command| COMMANDS.contains(&command.as_str()))
+        }
+        // 选项缺少参数值，按命令行处理以便报告错误
+        Err(_) => true,
+    }
+}
+
+// 命令行入口，返回进程退出码
+pub fn run(args: &[String]) -> i32 {
+    match dispatch(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("错误: {:#}", e);
+            1
+        }
+    }
+}
+
+fn dispatch(args: &[String]) -> Result<()> {
+    let args = Args::parse(args)?;
+    if args.flag("help") {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    let settings = args.settings()?;
+    let analyzer = settings.analyzer_options();
+    match args.positional.first().map(String::as_str) {
+        Some("index") => {
+            let dir = args.directory()?;
+            index(&dir, &analyzer)
+        }
+        Some("search") => {
+            let query = args.positional[1..].join(" ");
+            if query.trim().is_empty() {
+                bail!("缺少查询语句\n\n{}", USAGE);
+            }
+            search(&query, &args, &settings)
+        }
+        Some("stats") => stats(&analyzer),
+        Some("watch") => {
+            let dir = args.directory()?;
+            let interval = match args.value("interval") {
+                Some(v) => v.parse().context("--interval 应为秒数")?,
+                None => DEFAULT_INTERVAL_SECS,
+            };
+            watch(&dir, Duration::from_secs(interval.max(1)), &analyzer)
+        }
+        Some("serve") => serve(&args, &settings),
+        Some("help") => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => bail!("未知命令: {}\n\n{}", other, USAGE),
+        None => bail!("缺少命令\n\n{}", USAGE),
+    }
+}
+
+struct Args {
+    positional: Vec<String>,
+    // 带值的选项，按出现顺序保存，可重复
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(raw: &[String]) -> Result<Self> {
+        let mut args = Args {
+            positional: Vec::new(),
+            options: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut iter = raw.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "-h" {
+                args.flags.push("help".to_string());
+            } else if let Some(name) = arg.strip_prefix("--") {
+                // 同时支持 --limit 10 和 --limit=10
+                let (name, inline_value) = match name.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (name, None),
+                };
+                if VALUE_OPTIONS.contains(&name) {
+                    let value = match inline_value {
+                        Some(value) => value,
+                        None => iter
+                            .next()
+                            .cloned()
+                            .ok_or_else(|| anyhow!("--{} 缺少参数值", name))?,
+                    };
+                    args.options.push((name.to_string(), value));
+                } else {
+                    args.flags.push(name.to_string());
+                }
+            } else {
+                args.positional.push(arg.clone());
+            }
+        }
+        Ok(args)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.options
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn directory(&self) -> Result<PathBuf> {
+        let dir = self
+            .positional
+            .get(1)
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow!("缺少目录参数\n\n{}", USAGE))?;
+        if !dir.is_dir() {
+            bail!("不是目录: {}", dir.display());
+        }
+        Ok(dir)
+    }
+
+    // 图形界面保存的设置，再应用命令行中的选项
+    fn settings(&self) -> Result<AppSettings> {
+        let mut settings = AppSettings::load_saved();
+        if let Some(analyzer) = self.value("analyzer") {
+            settings.analyzer = match analyzer {
+                "jieba" => AnalyzerKind::Jieba,
+                "normalized" => AnalyzerKind::JiebaNormalized,
+                other => bail!("未知的分词器: {}", other),
+            };
+        }
+        Ok(settings)
+    }
+}
+
+// 进度和无法读取的文件输出到 stderr，stdout 留给结果
+struct ProgressPrinter;
+
+impl IndexObserver for ProgressPrinter {
+    fn progress(&self, done: usize, total: usize) {
+        eprint!("\r索引进度 {}/{}", done, total);
+    }
+
+    fn file_failed(&self, path: &Path, error: &anyhow::Error) {
+        eprintln!("\n无法读取 {}: {}", path.display(), error);
+    }
+}
+
+fn index(dir: &Path, analyzer: &AnalyzerOptions) -> Result<()> {
+    let indexer = Indexer::new(engine::index_dir(), analyzer.clone());
+    let searcher = indexer.index_directory(dir, &ProgressPrinter)?;
+    eprintln!();
+    eprintln!("已索引 {} 个文件", searcher.stats()?.num_docs);
+    Ok(())
+}
+
+fn search(query_str: &str, args: &Args, settings: &AppSettings) -> Result<()> {
+    let searcher = Indexer::new(engine::index_dir(), settings.analyzer_options()).open()?;
+
+    let mut options = settings.search_options();
+    options.limit = match args.value("limit") {
+        Some(v) => v.parse().context("--limit 应为正整数")?,
+        None => DEFAULT_LIMIT,
+    };
+    for filter in args.values("filter") {
+        let parsed = filter
+            .split_once('=')
+            .and_then(|(field, value)| FacetFilter::parse(field, value));
+        match parsed {
+            Some(filter) => options.filters.push(filter),
+            None => bail!(
+                "无法识别的筛选条件: {}（应为 folder=、file_type= 或 year=）",
+                filter
+            ),
+        }
+    }
+
+    let request = SearchRequest::new(query_str, options);
+    let response = searcher.search(&request, &(), Arc::default())?;
+    for issue in &response.ignored {
+        eprintln!("已忽略: {}", issue.message);
+    }
+    if args.flag("json") {
+        let mut out = std::io::stdout().lock();
+        export::write_to(
+            &mut out,
+            ExportFormat::JsonLines,
+            query_str,
+            &response.results,
+        )?;
+        out.flush()?;
+    } else {
+        print_results(&response.results);
+        eprintln!(
+            "共 {} 条结果，用时 {:.2?}",
+            response.results.len(),
+            response.duration
+        );
+    }
+    Ok(())
+}
+
+fn print_results(results: &[SearchResult]) {
+    // 输出到终端时用颜色标出命中词，重定向到文件时保持纯文本
+    let color = std::io::stdout().is_terminal();
+    for (i, result) in results.iter().enumerate() {
+        println!("{}. {}  ({:.2})", i + 1, result.path, result.score);
+        for snippet in &result.snippets {
+            println!("   {}", highlighted(snippet, color));
+        }
+    }
+}
+
+fn highlighted(snippet: &HighlightedText, color: bool) -> String {
+    let (start, end) = if color {
+        ("\x1b[1;31m", "\x1b[0m")
+    } else {
+        ("[", "]")
+    };
+    let mut text = String::new();
+    let mut cursor = 0;
+    for range in &snippet.highlights {
+        text.push_str(&snippet.text[cursor..range.start]);
+        text.push_str(start);
+        text.push_str(&snippet.text[range.clone()]);
+        text.push_str(end);
+        cursor = range.end;
+    }
+    text.push_str(&snippet.text[cursor..]);
+    text
+}
+
+fn stats(analyzer: &AnalyzerOptions) -> Result<()> {
+    let indexer = Indexer::new(engine::index_dir(), analyzer.clone());
+    let stats = indexer.open()?.stats()?;
+
+    let size: u64 = WalkDir::new(indexer.index_dir())
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum();
+    println!("索引目录: {}", indexer.index_dir().display());
+    println!("文档数:   {}", stats.num_docs);
+    println!("段数:     {}", stats.segments);
+    println!("占用空间: {:.1} MB", size as f64 / 1_048_576.0);
+
+    for summary in &stats.facets {
+        println!();
+        println!("{}:", summary.field.label());
+        for value in &summary.counts {
+            println!("  {:<24} {}", facets::facet_path(&value.facet), value.count);
+        }
+    }
+    Ok(())
+}
+
+fn serve(args: &Args, settings: &AppSettings) -> Result<()> {
+    let mut config = settings.api_config();
+    if let Some(port) = args.value("port") {
+        config.port = port.parse().context("--port 应为 0-65535 的端口号")?;
+    }
+    if let Some(token) = args.value("token") {
+        config.token = token.to_string();
+    }
+    let server = ApiServer::start(
+        config,
+        Arc::new(IndexService::new()),
+        &engine::index_dir(),
+        None,
+    )
+    .context("无法启动 API 服务")?;
+    eprintln!(
+        "API 已启动: http://{}/（用浏览器打开即为网页版），按 Ctrl+C 退出",
+        server.addr()
+    );
+    server.wait();
+    Ok(())
+}
+
+// 没有文件系统通知，定时比较各文件的修改时间和大小，有变化就重新索引
+fn watch(dir: &Path, interval: Duration, analyzer: &AnalyzerOptions) -> Result<()> {
+    let mut snapshot = scan(dir);
+    index(dir, analyzer)?;
+    let indexer = Indexer::new(engine::index_dir(), analyzer.clone());
+    eprintln!("正在监视 {}，按 Ctrl+C 退出", dir.display());
+    loop {
+        thread::sleep(interval);
+        let current = scan(dir);
+        let changed: Vec<PathBuf> = current
+            .iter()
+            .filter(|(path, state)| snapshot.get(*path) != Some(*state))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let removed: Vec<PathBuf> = snapshot
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned()
+            .collect();
+        if changed.is_empty() && removed.is_empty() {
+            continue;
+        }
+        eprintln!(
+            "检测到 {} 个文件变化、{} 个文件删除，更新索引",
+            changed.len(),
+            removed.len()
+        );
+        // 一次更新失败（如文件正被写入或索引被占用）不退出，下一轮重试
+        match indexer.update_files(dir, &changed, &removed, &ProgressPrinter) {
+            Ok(_) => {
+                eprintln!();
+                snapshot = current;
+            }
+            Err(e) => eprintln!("\n更新索引失败: {:#}", e),
+        }
+    }
+}
+
+fn scan(dir: &Path) -> BTreeMap<PathBuf, (Option<SystemTime>, u64)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        // 与建立索引时使用同一个过滤条件
+        .filter(|e| e.file_type().is_file() && indexer::is_docx(e.path()))
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            Some((
+                e.path().to_path_buf(),
+                (metadata.modified().ok(), metadata.len()),
+            ))
+        })
+        .collect()
+}
@@ -1,10 +1,47 @@
+pub use crate::search::analyzer::AnalyzerKind;
+use crate::api::ApiConfig;
+use crate::search::analyzer::AnalyzerOptions;
+use crate::search::query::SearchOptions;
+use crate::search::synonyms::SynonymTable;
 use eframe::egui;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+pub const DEFAULT_API_PORT: u16 = 7878;
+// 窗口标题，也是 eframe 保存应用状态的目录名
+pub const APP_ID: &str = "MyTxt";
 
 #[derive(Serialize, Deserialize)]
 #[serde(default)]
 pub struct AppSettings {
     pub theme: Theme,
+    pub analyzer: AnalyzerKind,
+    // jieba 用户词典原文
+    pub user_dict: String,
+    // 停用词表原文
+    pub stopwords: String,
+    // 同义词表原文，每行一组
+    pub synonyms: String,
+    pub expand_synonyms: bool,
+    pub lenient_query: bool,
+    // 输入时自动搜索
+    pub live_search: bool,
+    // 在结果列表右侧停靠预览面板
+    pub docked_preview: bool,
+    // 每次搜索最多返回的结果数
+    pub result_limit: usize,
+    // 摘要长度（字符）和每个结果的摘要条数
+    pub snippet_length: usize,
+    pub snippets_per_result: usize,
+    // 结果列表的分组方式
+    pub group_by: GroupBy,
+    pub sort_by: SortBy,
+    // 用户改过的快捷键，没有改过的使用默认值
+    pub shortcuts: BTreeMap<ShortcutAction, egui::KeyboardShortcut>,
+    // 本地 HTTP 接口，只监听 127.0.0.1；token 为空时不校验
+    pub api_enabled: bool,
+    pub api_port: u16,
+    pub api_token: String,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
@@ -13,19 +50,261 @@ pub enum Theme {
     Dark,
 }
 
+// 结果列表的分组方式
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GroupBy {
+    None,
+    // 所在文件夹
+    Folder,
+    // 索引时选择的根目录
+    Root,
+    Extension,
+    // 修改时间所在的月份
+    Month,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 5] = [
+        GroupBy::None,
+        GroupBy::Folder,
+        GroupBy::Root,
+        GroupBy::Extension,
+        GroupBy::Month,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupBy::None => "不分组",
+            GroupBy::Folder => "文件夹",
+            GroupBy::Root => "索引目录",
+            GroupBy::Extension => "文件类型",
+            GroupBy::Month => "修改月份",
+        }
+    }
+}
+
+// 结果列表的排列顺序，只调整显示顺序，取哪些结果仍按相关度
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SortBy {
+    Relevance,
+    Newest,
+    Oldest,
+    Path,
+}
+
+impl SortBy {
+    pub const ALL: [SortBy; 4] = [SortBy::Relevance, SortBy::Newest, SortBy::Oldest, SortBy::Path];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortBy::Relevance => "相关度",
+            SortBy::Newest => "最近修改",
+            SortBy::Oldest => "最早修改",
+            SortBy::Path => "路径",
+        }
+    }
+}
+
+// 可绑定快捷键的全局操作
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum ShortcutAction {
+    FocusSearch,
+    RunSearch,
+    StopSearch,
+    NextResult,
+    PreviousResult,
+    OpenFile,
+    OpenFolder,
+    Preview,
+    CopyPath,
+    CloseWindow,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 10] = [
+        ShortcutAction::FocusSearch,
+        ShortcutAction::RunSearch,
+        ShortcutAction::StopSearch,
+        ShortcutAction::NextResult,
+        ShortcutAction::PreviousResult,
+        ShortcutAction::OpenFile,
+        ShortcutAction::OpenFolder,
+        ShortcutAction::Preview,
+        ShortcutAction::CopyPath,
+        ShortcutAction::CloseWindow,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::FocusSearch => "聚焦搜索框",
+            ShortcutAction::RunSearch => "执行搜索",
+            ShortcutAction::StopSearch => "停止搜索",
+            ShortcutAction::NextResult => "下一条结果",
+            ShortcutAction::PreviousResult => "上一条结果",
+            ShortcutAction::OpenFile => "打开文件",
+            ShortcutAction::OpenFolder => "打开所在文件夹",
+            ShortcutAction::Preview => "在新窗口中预览",
+            ShortcutAction::CopyPath => "复制路径",
+            ShortcutAction::CloseWindow => "关闭窗口",
+        }
+    }
+
+    pub fn default_shortcut(self) -> egui::KeyboardShortcut {
+        use egui::{Key, KeyboardShortcut, Modifiers};
+        match self {
+            ShortcutAction::FocusSearch => KeyboardShortcut::new(Modifiers::COMMAND, Key::L),
+            ShortcutAction::RunSearch => KeyboardShortcut::new(Modifiers::COMMAND, Key::Enter),
+            ShortcutAction::StopSearch => KeyboardShortcut::new(Modifiers::NONE, Key::Escape),
+            ShortcutAction::NextResult => KeyboardShortcut::new(Modifiers::NONE, Key::ArrowDown),
+            ShortcutAction::PreviousResult => KeyboardShortcut::new(Modifiers::NONE, Key::ArrowUp),
+            ShortcutAction::OpenFile => KeyboardShortcut::new(Modifiers::COMMAND, Key::O),
+            ShortcutAction::OpenFolder => {
+                KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::O)
+            }
+            ShortcutAction::Preview => KeyboardShortcut::new(Modifiers::COMMAND, Key::P),
+            ShortcutAction::CopyPath => {
+                KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::C)
+            }
+            ShortcutAction::CloseWindow => KeyboardShortcut::new(Modifiers::COMMAND, Key::W),
+        }
+    }
+}
+
+// 图形界面保存的应用状态中只取出设置，其余字段忽略
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SavedApp {
+    settings: AppSettings,
+}
+
 impl AppSettings {
+    // 图形界面保存的设置，命令行用它与界面使用相同的分词和搜索设置；
+    // 没有保存过或无法读取时为默认设置
+    pub fn load_saved() -> Self {
+        eframe::storage_dir(APP_ID)
+            .and_then(|dir| std::fs::read_to_string(dir.join("app.ron")).ok())
+            .and_then(|text| Self::from_saved_state(&text))
+            .unwrap_or_default()
+    }
+
+    // eframe 的状态文件是键到 RON 文本的映射，应用本身保存在 APP_KEY 下
+    fn from_saved_state(text: &str) -> Option<Self> {
+        let kv: HashMap<String, String> = ron::from_str(text).ok()?;
+        let app: SavedApp = ron::from_str(kv.get(eframe::APP_KEY)?).ok()?;
+        Some(app.settings)
+    }
+
+    pub fn shortcut(&self, action: ShortcutAction) -> egui::KeyboardShortcut {
+        self.shortcuts
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_shortcut())
+    }
+
     pub fn get_visuals(&self) -> egui::Visuals {
         match self.theme {
             Theme::Light => egui::Visuals::light(),
             Theme::Dark => egui::Visuals::dark(),
         }
     }
+
+    pub fn analyzer_options(&self) -> AnalyzerOptions {
+        AnalyzerOptions {
+            kind: self.analyzer,
+            user_dict: self.user_dict.clone(),
+            stopwords: self.stopwords.clone(),
+        }
+    }
+
+    pub fn api_config(&self) -> ApiConfig {
+        ApiConfig {
+            port: self.api_port,
+            token: self.api_token.clone(),
+            search_options: self.search_options(),
+            analyzer_options: self.analyzer_options(),
+        }
+    }
+
+    pub fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            synonyms: self
+                .expand_synonyms
+                .then(|| SynonymTable::parse(&self.synonyms)),
+            lenient: self.lenient_query,
+            limit: self.result_limit,
+            snippet_length: self.snippet_length,
+            snippets_per_result: self.snippets_per_result,
+            filters: Vec::new(),
+        }
+    }
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             theme: Theme::Light,
+            analyzer: AnalyzerKind::JiebaNormalized,
+            user_dict: String::new(),
+            stopwords: String::new(),
+            synonyms: String::new(),
+            expand_synonyms: true,
+            lenient_query: false,
+            live_search: true,
+            docked_preview: true,
+            result_limit: 100,
+            snippet_length: 120,
+            snippets_per_result: 1,
+            group_by: GroupBy::None,
+            sort_by: SortBy::Relevance,
+            shortcuts: BTreeMap::new(),
+            api_enabled: false,
+            api_port: DEFAULT_API_PORT,
+            api_token: String::new(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 与 MyApp 一样，设置之外还有其他字段
+    #[derive(Serialize)]
+    struct App {
+        search_query: String,
+        settings: AppSettings,
+    }
+
+    fn saved_state(app: &App) -> String {
+        let kv = HashMap::from([
+            (eframe::APP_KEY.to_string(), ron::to_string(app).unwrap()),
+            ("egui".to_string(), "()".to_string()),
+        ]);
+        ron::ser::to_string_pretty(&kv, Default::default()).unwrap()
+    }
+
+    #[test]
+    fn reads_settings_from_saved_state() {
+        let app = App {
+            search_query: "合同".to_string(),
+            settings: AppSettings {
+                analyzer: AnalyzerKind::Jieba,
+                user_dict: "云计算 5".to_string(),
+                stopwords: "的".to_string(),
+                synonyms: "电脑 计算机".to_string(),
+                expand_synonyms: true,
+                ..AppSettings::default()
+            },
+        };
+        let settings = AppSettings::from_saved_state(&saved_state(&app)).unwrap();
+        assert_eq!(settings.analyzer_options(), app.settings.analyzer_options());
+        assert_eq!(settings.synonyms, "电脑 计算机");
+        assert!(settings.search_options().synonyms.is_some());
+    }
+
+    #[test]
+    fn unreadable_state_has_no_settings() {
+        assert!(AppSettings::from_saved_state("not ron").is_none());
+        assert!(AppSettings::from_saved_state("{}").is_none());
+    }
+}
@@ -0,0 +1,90 @@
+use crate::utils::file_ops::{FileOpStatus, FileOperation};
+use eframe::egui;
+
+pub enum BatchEvent {
+    Run(FileOperation),
+    SelectAll,
+    ClearSelection,
+    // 关闭已完成操作的提示
+    DismissStatus,
+}
+
+// 结果列表上方的多选操作栏，Ctrl/Shift+点击结果可多选
+pub fn show(ui: &mut egui::Ui, marked: usize, status: Option<&FileOpStatus>) -> Option<BatchEvent> {
+    let mut event = None;
+    let busy = status.is_some_and(|s| !s.finished);
+    ui.horizontal(|ui| {
+        ui.label(format!("已选 {} 个文件", marked));
+        let enabled = marked > 0 && !busy;
+        if ui
+            .add_enabled(enabled, egui::Button::new("复制到…"))
+            .on_hover_text("保留索引目录下的相对路径")
+            .clicked()
+        {
+            event = Some(BatchEvent::Run(FileOperation::Copy));
+        }
+        if ui
+            .add_enabled(enabled, egui::Button::new("移动到…"))
+            .on_hover_text("保留索引目录下的相对路径")
+            .clicked()
+        {
+            event = Some(BatchEvent::Run(FileOperation::Move));
+        }
+        if ui
+            .add_enabled(enabled, egui::Button::new("打包为 zip…"))
+            .clicked()
+        {
+            event = Some(BatchEvent::Run(FileOperation::Zip));
+        }
+        if ui.small_button("全选").clicked() {
+            event = Some(BatchEvent::SelectAll);
+        }
+        if marked > 0 && ui.small_button("取消选择").clicked() {
+            event = Some(BatchEvent::ClearSelection);
+        }
+    });
+
+    let Some(status) = status else {
+        return event;
+    };
+    let label = status.operation.label();
+    if !status.finished {
+        let progress = status.done as f32 / status.total.max(1) as f32;
+        ui.add(
+            egui::ProgressBar::new(progress)
+                .text(format!("正在{} {}/{}", label, status.done, status.total)),
+        );
+        return event;
+    }
+    ui.horizontal(|ui| {
+        let succeeded = status.done.saturating_sub(status.errors.len());
+        if let Some(fatal) = &status.fatal {
+            ui.label(
+                egui::RichText::new(format!("{}失败: {}", label, fatal)).color(egui::Color32::RED),
+            );
+        } else {
+            ui.label(format!("{}完成：成功 {} 个", label, succeeded));
+        }
+        if !status.errors.is_empty() {
+            ui.label(
+                egui::RichText::new(format!("失败 {} 个", status.errors.len()))
+                    .color(egui::Color32::RED),
+            )
+            .on_hover_ui(|ui| {
+                for error in &status.errors {
+                    ui.label(error);
+                }
+            });
+        }
+        if status.operation == FileOperation::Move && succeeded > 0 {
+            ui.label(
+                egui::RichText::new("文件已移动，请重新索引")
+                    .color(egui::Color32::from_rgb(230, 140, 0)),
+            );
+        }
+        if ui.small_button("✖").clicked() {
+            event = Some(BatchEvent::DismissStatus);
+        }
+    });
+    event
+}
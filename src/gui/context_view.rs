@@ -1,10 +1,45 @@
+use crate::gui::find_bar::{FindBar, FindEvent};
+use crate::utils::docx_document::{Block, FormattedDocument, Paragraph, ParagraphKind};
 use eframe::egui;
+use eframe::egui::text::LayoutJob;
+use std::ops::Range;
+
+// 表格单元格的最大宽度
+const TABLE_CELL_WIDTH: f32 = 240.0;
+
+// 预览窗口中的一行显示内容（长行会被折成多行）
+struct Row {
+    // 所属的原文行号，从 0 开始
+    line: usize,
+    // 该行在全文中的字节范围
+    range: Range<usize>,
+    // 是否为原文行的第一段，只有第一段显示行号
+    first: bool,
+}
 
 pub struct ContextView {
     id: egui::Id,
     pub path: String,
     content: String,
+    // 原文行数，决定行号栏的宽度
+    line_count: usize,
+    // docx 的格式化结构，有则按文档样式显示，否则按行显示纯文本
+    document: Option<FormattedDocument>,
     open: bool,
+    // 由分词器给出的主查询命中位置（字节范围，已排序且不重叠），在后台算好后才填入
+    query_matches: Vec<Range<usize>>,
+    find: FindBar,
+    // 当前高亮的命中：查找框有内容时是查找结果，否则是主查询的命中
+    matches: Vec<Range<usize>>,
+    current: usize,
+    rows: Vec<Row>,
+    // rows 按多少个字符一行折出来的，宽度变化时重建
+    row_chars: usize,
+    // 格式化视图中每块的高度（含块间距），显示过的为实际高度，其余为估计值
+    block_heights: Vec<f32>,
+    // block_heights 是按多宽估计的，宽度变化时重新估计
+    block_width: f32,
+    scroll_to_current: bool,
 }
 
 impl ContextView {
@@ -12,48 +47,496 @@ impl ContextView {
         Self {
             id: egui::Id::new(&path),
             path,
+            line_count: content.lines().count().max(1),
             content,
+            document: None,
             open: true,
+            query_matches: Vec::new(),
+            find: FindBar::default(),
+            matches: Vec::new(),
+            current: 0,
+            rows: Vec::new(),
+            row_chars: 0,
+            block_heights: Vec::new(),
+            block_width: 0.0,
+            // 打开时定位到第一个命中
+            scroll_to_current: true,
         }
     }
 
+    pub fn with_document(mut self, document: FormattedDocument) -> Self {
+        self.document = Some(document);
+        self
+    }
+
+    // 停靠在预览面板中的实例，与同一文件的浮动窗口区分开，避免控件 id 冲突
+    pub fn docked(mut self) -> Self {
+        self.id = egui::Id::new(("docked_preview", &self.path));
+        self
+    }
+
+    // 主查询的命中到达后定位到第一个；查找框有内容时仍显示查找结果
+    pub fn set_query_matches(&mut self, matches: Vec<Range<usize>>) {
+        self.query_matches = matches;
+        if !self.find.is_active() {
+            self.refresh_matches();
+        }
+    }
+
+    pub fn id(&self) -> egui::Id {
+        self.id
+    }
+
     pub fn is_open(&self) -> &bool {
         &self.open
     }
 
-    pub fn draw(&mut self, ctx: &egui::Context, search_query: &str) {
+    pub fn draw(&mut self, ctx: &egui::Context) {
+        // 只有最上层的预览窗口响应快捷键
+        let keyboard_active =
+            ctx.top_layer_id() == Some(egui::LayerId::new(egui::Order::Middle, self.id));
         let mut is_open = self.open;
         egui::Window::new(&self.path)
             .id(self.id)
             .default_size([600.0, 400.0])
             .open(&mut is_open)
-            .show(ctx, |ui| {
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    ui.horizontal_wrapped(|ui| {
-                        ui.spacing_mut().item_spacing.x = 0.0;
-                        // A simple way to highlight search query. Case-insensitive.
-                        let lower_query = search_query.to_lowercase();
-                        let mut last_end = 0;
-                        for (start, _)
-                            in self.content.to_lowercase().match_indices(&lower_query)
-                        {
-                            if start > last_end {
-                                ui.label(&self.content[last_end..start]);
+            .show(ctx, |ui| self.show_contents(ui, keyboard_active));
+        self.open = is_open;
+    }
+
+    fn select(&mut self, index: usize) {
+        self.current = index;
+        self.scroll_to_current = true;
+    }
+
+    fn refresh_matches(&mut self) {
+        self.matches = self
+            .find
+            .matches(&self.content)
+            .unwrap_or_else(|| self.query_matches.clone());
+        self.select(0);
+    }
+
+    fn next_match(&mut self) {
+        if !self.matches.is_empty() {
+            self.select((self.current + 1) % self.matches.len());
+        }
+    }
+
+    fn previous_match(&mut self) {
+        if !self.matches.is_empty() {
+            self.select((self.current + self.matches.len() - 1) % self.matches.len());
+        }
+    }
+
+    pub fn show_contents(&mut self, ui: &mut egui::Ui, keyboard_active: bool) {
+        if keyboard_active {
+            let (next, previous, find) = ui.input(|i| {
+                let f3 = i.key_pressed(egui::Key::F3);
+                (
+                    f3 && !i.modifiers.shift,
+                    f3 && i.modifiers.shift,
+                    i.modifiers.command && i.key_pressed(egui::Key::F),
+                )
+            });
+            if next {
+                self.next_match();
+            }
+            if previous {
+                self.previous_match();
+            }
+            if find {
+                self.find.focus();
+            }
+        }
+
+        match self.find.show(ui) {
+            Some(FindEvent::Changed) => self.refresh_matches(),
+            Some(FindEvent::Next) => self.next_match(),
+            Some(FindEvent::Previous) => self.previous_match(),
+            None => {}
+        }
+
+        ui.horizontal(|ui| {
+            let source = if self.find.is_active() {
+                "查找"
+            } else {
+                "关键词"
+            };
+            if self.matches.is_empty() {
+                ui.label(format!("{}: 无匹配", source));
+            } else {
+                ui.label(format!(
+                    "{}: 匹配 {} / {}",
+                    source,
+                    self.current + 1,
+                    self.matches.len()
+                ));
+            }
+            let has_matches = !self.matches.is_empty();
+            if ui
+                .add_enabled(has_matches, egui::Button::new("▲ 上一个"))
+                .clicked()
+            {
+                self.previous_match();
+            }
+            if ui
+                .add_enabled(has_matches, egui::Button::new("▼ 下一个"))
+                .clicked()
+            {
+                self.next_match();
+            }
+            ui.label(
+                egui::RichText::new("F3 / Shift+F3")
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+        });
+        ui.separator();
+
+        if let Some(document) = self.document.take() {
+            let scroll_target = std::mem::take(&mut self.scroll_to_current)
+                .then(|| self.matches.get(self.current).map(|m| m.start))
+                .flatten();
+            self.show_document(ui, &document, scroll_target);
+            self.document = Some(document);
+            return;
+        }
+
+        let font_id = egui::TextStyle::Body.resolve(ui.style());
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        let gutter_width = font_id.size * 0.6 * (self.line_count.to_string().len() as f32 + 1.0);
+
+        // 以汉字宽度估算每行能放下的字符数，保证折行后不会超出窗口
+        let text_width = (ui.available_width() - gutter_width - 16.0).max(font_id.size * 10.0);
+        let row_chars = (text_width / font_id.size).floor() as usize;
+        if row_chars != self.row_chars {
+            self.rebuild_rows(row_chars);
+        }
+
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_source(self.id.with("rows"))
+            .auto_shrink([false, false]);
+        if self.scroll_to_current {
+            self.scroll_to_current = false;
+            if let Some(row) = self.current_row() {
+                let row_height_with_spacing = row_height + ui.spacing().item_spacing.y;
+                let offset = row as f32 * row_height_with_spacing - ui.available_height() / 2.0;
+                scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.0));
+            }
+        }
+
+        let current_match = self.matches.get(self.current).cloned();
+        scroll_area.show_rows(ui, row_height, self.rows.len(), |ui, row_range| {
+            for row in &self.rows[row_range] {
+                ui.horizontal(|ui| {
+                    let line_number = if row.first {
+                        (row.line + 1).to_string()
+                    } else {
+                        String::new()
+                    };
+                    ui.add_sized(
+                        [gutter_width, row_height],
+                        egui::Label::new(
+                            egui::RichText::new(line_number).color(egui::Color32::GRAY),
+                        ),
+                    );
+                    ui.label(self.row_job(row, &font_id, current_match.as_ref(), ui));
+                });
+            }
+        });
+    }
+
+    fn rebuild_rows(&mut self, row_chars: usize) {
+        self.row_chars = row_chars;
+        self.rows.clear();
+        let mut line_start = 0;
+        for (line, text) in self.content.split('\n').enumerate() {
+            let mut row_start = line_start;
+            let mut first = true;
+            for (count, (offset, _)) in text.char_indices().enumerate() {
+                if count > 0 && count % row_chars == 0 {
+                    self.rows.push(Row {
+                        line,
+                        range: row_start..line_start + offset,
+                        first,
+                    });
+                    row_start = line_start + offset;
+                    first = false;
+                }
+            }
+            self.rows.push(Row {
+                line,
+                range: row_start..line_start + text.len(),
+                first,
+            });
+            line_start += text.len() + 1;
+        }
+    }
+
+    fn current_row(&self) -> Option<usize> {
+        let start = self.matches.get(self.current)?.start;
+        let row = self.rows.partition_point(|r| r.range.start <= start);
+        Some(row.saturating_sub(1))
+    }
+
+    // One row of text with every match coloured and the current match marked.
+    fn row_job(
+        &self,
+        row: &Row,
+        font_id: &egui::FontId,
+        current_match: Option<&Range<usize>>,
+        ui: &egui::Ui,
+    ) -> LayoutJob {
+        let normal = egui::TextFormat {
+            font_id: font_id.clone(),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+        let mut job = LayoutJob::default();
+        self.append_highlighted(&mut job, row.range.clone(), normal, current_match);
+        job
+    }
+
+    // 把 range 内的文字加入 job，命中部分在 format 的基础上改为高亮样式
+    fn append_highlighted(
+        &self,
+        job: &mut LayoutJob,
+        range: Range<usize>,
+        format: egui::TextFormat,
+        current_match: Option<&Range<usize>>,
+    ) {
+        let highlighted = egui::TextFormat {
+            color: egui::Color32::RED,
+            ..format.clone()
+        };
+        let current = egui::TextFormat {
+            color: egui::Color32::BLACK,
+            background: egui::Color32::from_rgb(255, 210, 0),
+            ..format.clone()
+        };
+
+        let mut cursor = range.start;
+        let first = self.matches.partition_point(|m| m.end <= range.start);
+        for m in self.matches[first..]
+            .iter()
+            .take_while(|m| m.start < range.end)
+        {
+            let start = m.start.max(range.start);
+            let end = m.end.min(range.end);
+            if start > cursor {
+                job.append(&self.content[cursor..start], 0.0, format.clone());
+            }
+            let format = if current_match == Some(m) {
+                current.clone()
+            } else {
+                highlighted.clone()
+            };
+            job.append(&self.content[start..end], 0.0, format);
+            cursor = end;
+        }
+        if cursor < range.end {
+            job.append(&self.content[cursor..range.end], 0.0, format);
+        }
+    }
+
+    // 只显示可见范围内的块：每块的位置由各块高度累加得到，
+    // 显示过的块记下实际高度，其余按字数估计
+    fn show_document(
+        &mut self,
+        ui: &mut egui::Ui,
+        document: &FormattedDocument,
+        scroll_target: Option<usize>,
+    ) {
+        let font_size = egui::TextStyle::Body.resolve(ui.style()).size;
+        let row_height = ui.text_style_height(&egui::TextStyle::Body);
+        let spacing = ui.spacing().item_spacing;
+        let gutter_width = font_size * 0.6 * (document.blocks.len().to_string().len() as f32 + 1.0);
+        let text_width =
+            (ui.available_width() - gutter_width - spacing.x - 16.0).max(font_size * 10.0);
+        if text_width != self.block_width || self.block_heights.len() != document.blocks.len() {
+            self.block_width = text_width;
+            self.block_heights = document
+                .blocks
+                .iter()
+                .map(|block| self.estimate_height(block, text_width, font_size, row_height))
+                .map(|height| height + spacing.y)
+                .collect();
+        }
+        let tops: Vec<f32> = self
+            .block_heights
+            .iter()
+            .scan(0.0, |y, height| {
+                let top = *y;
+                *y += height;
+                Some(top)
+            })
+            .collect();
+        let total_height: f32 = self.block_heights.iter().sum();
+
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_source(self.id.with("document"))
+            .auto_shrink([false, false]);
+        // 先滚动到命中所在块的估计位置，块显示出来后再由 scroll_to_me 精确定位
+        if let Some(block) = scroll_target.and_then(|target| target_block(document, target)) {
+            let offset = tops[block] - ui.available_height() / 2.0;
+            scroll_area = scroll_area.vertical_scroll_offset(offset.max(0.0));
+        }
+
+        let current_match = self.matches.get(self.current).cloned();
+        scroll_area.show_viewport(ui, |ui, viewport| {
+            ui.set_height(total_height);
+            let origin = ui.max_rect().min;
+            let width = ui.max_rect().width();
+            let first = tops
+                .partition_point(|&top| top <= viewport.min.y)
+                .saturating_sub(1);
+            for (i, block) in document.blocks.iter().enumerate().skip(first) {
+                if tops[i] > viewport.max.y {
+                    break;
+                }
+                let rect = egui::Rect::from_min_size(
+                    origin + egui::vec2(0.0, tops[i]),
+                    egui::vec2(width, self.block_heights[i]),
+                );
+                let response = ui.allocate_ui_at_rect(rect, |ui| {
+                    ui.horizontal_top(|ui| {
+                        ui.add_sized(
+                            [gutter_width, row_height],
+                            egui::Label::new(
+                                egui::RichText::new((i + 1).to_string()).color(egui::Color32::GRAY),
+                            ),
+                        );
+                        ui.vertical(|ui| {
+                            self.show_block(ui, i, block, current_match.as_ref(), scroll_target)
+                        });
+                    });
+                });
+                let height = response.response.rect.height() + spacing.y;
+                if (height - self.block_heights[i]).abs() > 0.5 {
+                    self.block_heights[i] = height;
+                    ui.ctx().request_repaint();
+                }
+            }
+        });
+    }
+
+    fn show_block(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        block: &Block,
+        current_match: Option<&Range<usize>>,
+        scroll_target: Option<usize>,
+    ) {
+        match block {
+            Block::Paragraph(p) => {
+                self.show_paragraph(ui, p, current_match, scroll_target);
+            }
+            Block::Table(rows) => {
+                egui::Grid::new(self.id.with(("table", index)))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for row in rows {
+                            for cell in row {
+                                ui.vertical(|ui| {
+                                    ui.set_max_width(TABLE_CELL_WIDTH);
+                                    for p in cell {
+                                        self.show_paragraph(ui, p, current_match, scroll_target);
+                                    }
+                                });
                             }
-                            let end = start + search_query.len();
-                            ui.label(
-                                egui::RichText::new(&self.content[start..end])
-                                    .color(egui::Color32::RED)
-                                    .strong(),
-                            );
-                            last_end = end;
-                        }
-                        if last_end < self.content.len() {
-                            ui.label(&self.content[last_end..]);
+                            ui.end_row();
                         }
                     });
-                });
-            });
-        self.open = is_open;
+            }
+        }
+    }
+
+    // 以汉字宽度估计折行后的高度
+    fn estimate_height(&self, block: &Block, width: f32, font_size: f32, row_height: f32) -> f32 {
+        let lines = |p: &Paragraph, width: f32| {
+            let chars = self.content[p.range.clone()].chars().count();
+            let per_line = ((width / font_size).floor() as usize).max(1);
+            let scale = match p.kind {
+                ParagraphKind::Heading(_) => 1.8,
+                _ => 1.0,
+            };
+            chars.div_ceil(per_line).max(1) as f32 * scale
+        };
+        match block {
+            Block::Paragraph(p) => lines(p, width) * row_height,
+            Block::Table(rows) => rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| cell.iter().map(|p| lines(p, TABLE_CELL_WIDTH)).sum())
+                        .fold(1.0, f32::max)
+                        * row_height
+                })
+                .sum(),
+        }
     }
+
+    fn show_paragraph(
+        &self,
+        ui: &mut egui::Ui,
+        paragraph: &Paragraph,
+        current_match: Option<&Range<usize>>,
+        scroll_target: Option<usize>,
+    ) {
+        let body = egui::TextStyle::Body.resolve(ui.style());
+        let normal = egui::TextFormat {
+            font_id: body.clone(),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+
+        let mut job = LayoutJob::default();
+        let mut base = normal.clone();
+        match paragraph.kind {
+            ParagraphKind::Body => {}
+            ParagraphKind::Heading(level) => {
+                let scale = (1.8 - 0.15 * (level as f32 - 1.0)).max(1.0);
+                base.font_id = egui::FontId::new(body.size * scale, body.family.clone());
+                base.color = ui.visuals().strong_text_color();
+            }
+            ParagraphKind::ListItem { level, number } => {
+                let marker = match number {
+                    Some(n) => format!("{}. ", n),
+                    None => "• ".to_string(),
+                };
+                job.append(&marker, body.size * 1.5 * (level as f32 + 1.0), normal);
+            }
+        }
+
+        for span in &paragraph.spans {
+            let mut format = base.clone();
+            if span.bold {
+                // egui 没有粗体字形，用强调色代替
+                format.color = ui.visuals().strong_text_color();
+            }
+            format.italics = span.italic;
+            self.append_highlighted(&mut job, span.range.clone(), format, current_match);
+        }
+
+        let response = ui.label(job);
+        if let Some(target) = scroll_target {
+            if paragraph.range.contains(&target) {
+                response.scroll_to_me(Some(egui::Align::Center));
+            }
+        }
+    }
+}
+
+// 命中位置所在的块
+fn target_block(document: &FormattedDocument, target: usize) -> Option<usize> {
+    document.blocks.iter().position(|block| match block {
+        Block::Paragraph(p) => target <= p.range.end,
+        Block::Table(rows) => rows
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|p| target <= p.range.end),
+    })
 }
@@ -0,0 +1,69 @@
+use crate::search::facets::{self, FacetFilter, FacetSummary};
+use eframe::egui;
+
+pub enum FacetEvent {
+    // 按某一项筛选，替换同一维度上已有的条件
+    Select(FacetFilter),
+    // 去掉 filters 中对应下标的条件
+    Remove(usize),
+    Clear,
+}
+
+// 结果分布侧栏：上方是已选的筛选条件，下方是各维度的命中数，点击即可缩小范围
+pub fn show(
+    ui: &mut egui::Ui,
+    summaries: &[FacetSummary],
+    filters: &[FacetFilter],
+) -> Option<FacetEvent> {
+    let mut event = None;
+    ui.heading("结果分布");
+
+    if !filters.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            for (i, filter) in filters.iter().enumerate() {
+                if ui
+                    .small_button(format!("{} ✖", filter.label()))
+                    .on_hover_text("移除此筛选")
+                    .clicked()
+                {
+                    event = Some(FacetEvent::Remove(i));
+                }
+            }
+        });
+        if ui.small_button("清除全部筛选").clicked() {
+            event = Some(FacetEvent::Clear);
+        }
+    }
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for summary in summaries {
+            egui::CollapsingHeader::new(summary.field.label())
+                .default_open(true)
+                .show(ui, |ui| {
+                    if summary.counts.is_empty() {
+                        ui.label(egui::RichText::new("无").color(egui::Color32::GRAY));
+                    }
+                    for value in &summary.counts {
+                        let selected = filters
+                            .iter()
+                            .any(|f| f.field == summary.field && f.facet == value.facet);
+                        let text =
+                            format!("{} ({})", facets::facet_label(&value.facet), value.count);
+                        if ui
+                            .selectable_label(selected, text)
+                            .on_hover_text(facets::facet_path(&value.facet))
+                            .clicked()
+                            && !selected
+                        {
+                            event = Some(FacetEvent::Select(FacetFilter {
+                                field: summary.field,
+                                facet: value.facet.clone(),
+                            }));
+                        }
+                    }
+                });
+        }
+    });
+    event
+}
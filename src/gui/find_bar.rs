@@ -0,0 +1,90 @@
+use crate::search::highlight::{self, FindOptions};
+use eframe::egui;
+use std::ops::Range;
+
+// 预览窗口自己的查找框，每个窗口一份，不影响主搜索框
+#[derive(Default)]
+pub struct FindBar {
+    text: String,
+    options: FindOptions,
+    // 正则表达式写错时的提示
+    error: Option<String>,
+    request_focus: bool,
+}
+
+pub enum FindEvent {
+    // 查找条件变了，需要重新计算命中
+    Changed,
+    // 在输入框中按下回车
+    Next,
+    Previous,
+}
+
+impl FindBar {
+    pub fn is_active(&self) -> bool {
+        !self.text.is_empty()
+    }
+
+    pub fn focus(&mut self) {
+        self.request_focus = true;
+    }
+
+    // None 表示查找框为空，应退回到主查询的命中
+    pub fn matches(&mut self, content: &str) -> Option<Vec<Range<usize>>> {
+        if self.text.is_empty() {
+            self.error = None;
+            return None;
+        }
+        match highlight::find_ranges(content, &self.text, self.options) {
+            Ok(matches) => {
+                self.error = None;
+                Some(matches)
+            }
+            Err(e) => {
+                self.error = Some(format!("正则表达式有误: {}", e));
+                Some(Vec::new())
+            }
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<FindEvent> {
+        let mut event = None;
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.text)
+                    .hint_text("在本文中查找 (Ctrl+F)")
+                    .desired_width(200.0),
+            );
+            if std::mem::take(&mut self.request_focus) {
+                response.request_focus();
+            }
+            if response.changed() {
+                event = Some(FindEvent::Changed);
+            }
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                event = Some(if ui.input(|i| i.modifiers.shift) {
+                    FindEvent::Previous
+                } else {
+                    FindEvent::Next
+                });
+                // 回车后保持焦点，方便连续跳转
+                response.request_focus();
+            }
+
+            let options = self.options;
+            ui.toggle_value(&mut self.options.case_sensitive, "Aa")
+                .on_hover_text("区分大小写");
+            ui.toggle_value(&mut self.options.whole_word, "全词")
+                .on_hover_text("全词匹配");
+            ui.toggle_value(&mut self.options.regex, ".*")
+                .on_hover_text("正则表达式");
+            if options != self.options {
+                event = Some(FindEvent::Changed);
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.label(egui::RichText::new(error).small().color(egui::Color32::RED));
+        }
+        event
+    }
+}
@@ -0,0 +1,170 @@
+use crate::history::{self, SearchHistory};
+use eframe::egui;
+
+pub enum HistoryEvent {
+    // 把历史中的查询填入搜索框并执行
+    Run(String),
+    RunSaved(usize),
+    // 以给定名称保存当前的查询、筛选和排列方式
+    SaveCurrent(String),
+    TogglePin(usize),
+    DeleteSaved(usize),
+    ClearHistory,
+}
+
+// 搜索框下方的下拉层：已保存的搜索和最近搜索
+#[derive(Default)]
+pub struct HistoryMenu {
+    open: bool,
+    save_name: String,
+}
+
+impl HistoryMenu {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    // anchor 为搜索框所在的一行，下拉层贴在它下方
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        anchor: egui::Rect,
+        history: &SearchHistory,
+        can_save: bool,
+    ) -> Option<HistoryEvent> {
+        if !self.open {
+            return None;
+        }
+        let mut event = None;
+        let area = egui::Area::new(egui::Id::new("history_menu"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(anchor.left_bottom())
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(anchor.width().max(260.0));
+                    self.show_saved(ui, history, can_save, &mut event);
+                    ui.separator();
+                    show_recent(ui, history, &mut event);
+                });
+            });
+
+        // 点击下拉层和搜索框以外的地方、按 Esc 或执行了搜索后收起
+        let clicked_outside = ctx.input(|i| {
+            i.pointer.any_click()
+                && i.pointer
+                    .interact_pos()
+                    .is_some_and(|p| !area.response.rect.contains(p) && !anchor.contains(p))
+        });
+        let escape = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        let ran = matches!(
+            event,
+            Some(HistoryEvent::Run(_) | HistoryEvent::RunSaved(_))
+        );
+        if clicked_outside || escape || ran {
+            self.open = false;
+        }
+        event
+    }
+
+    fn show_saved(
+        &mut self,
+        ui: &mut egui::Ui,
+        history: &SearchHistory,
+        can_save: bool,
+        event: &mut Option<HistoryEvent>,
+    ) {
+        ui.label(egui::RichText::new("已保存的搜索").strong());
+        if history.saved.is_empty() {
+            ui.label(
+                egui::RichText::new("暂无")
+                    .small()
+                    .color(egui::Color32::GRAY),
+            );
+        }
+        for (i, saved) in history.saved.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui
+                    .selectable_label(saved.pinned, "📌")
+                    .on_hover_text("固定到左侧面板")
+                    .clicked()
+                {
+                    *event = Some(HistoryEvent::TogglePin(i));
+                }
+                if ui.small_button("🗑").on_hover_text("删除").clicked() {
+                    *event = Some(HistoryEvent::DeleteSaved(i));
+                }
+                if ui.link(&saved.name).on_hover_text(&saved.query).clicked() {
+                    *event = Some(HistoryEvent::RunSaved(i));
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.save_name)
+                    .hint_text("名称")
+                    .desired_width(120.0),
+            );
+            let enabled = can_save && !self.save_name.trim().is_empty();
+            if ui
+                .add_enabled(enabled, egui::Button::new("保存当前搜索"))
+                .clicked()
+            {
+                let name = std::mem::take(&mut self.save_name);
+                *event = Some(HistoryEvent::SaveCurrent(name.trim().to_string()));
+            }
+        });
+    }
+}
+
+fn show_recent(ui: &mut egui::Ui, history: &SearchHistory, event: &mut Option<HistoryEvent>) {
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("最近搜索").strong());
+        if !history.entries.is_empty() && ui.small_button("清除").clicked() {
+            *event = Some(HistoryEvent::ClearHistory);
+        }
+    });
+    if history.entries.is_empty() {
+        ui.label(
+            egui::RichText::new("暂无")
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+        return;
+    }
+    egui::ScrollArea::vertical()
+        .max_height(240.0)
+        .show(ui, |ui| {
+            for entry in &history.entries {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add(egui::Button::new(&entry.query).frame(false))
+                        .clicked()
+                    {
+                        *event = Some(HistoryEvent::Run(entry.query.clone()));
+                    }
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "{} 条 · {}",
+                            entry.results,
+                            history::relative_time(entry.time)
+                        ))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                    );
+                });
+            }
+        });
+}
+
+// 左侧面板中固定的已保存搜索，一键执行
+pub fn show_pinned(ui: &mut egui::Ui, history: &SearchHistory) -> Option<HistoryEvent> {
+    let mut event = None;
+    ui.horizontal_wrapped(|ui| {
+        for (i, saved) in history.saved.iter().enumerate() {
+            if saved.pinned && ui.button(&saved.name).on_hover_text(&saved.query).clicked() {
+                event = Some(HistoryEvent::RunSaved(i));
+            }
+        }
+    });
+    event
+}
@@ -1,12 +1,21 @@
+pub mod batch_bar;
 pub mod context_view;
+pub mod facet_panel;
+pub mod find_bar;
+pub mod history_menu;
+pub mod query_builder_view;
+pub mod query_feedback;
+pub mod result_list;
 pub mod settings_view;
 
 use self::context_view::ContextView;
+use self::query_builder_view::QueryBuilderView;
 use self::settings_view::SettingsView;
 
 pub enum AppWindow {
     Context(ContextView),
     Settings(SettingsView),
+    QueryBuilder(QueryBuilderView),
 }
 
 impl AppWindow {
@@ -14,13 +23,24 @@ impl AppWindow {
         match self {
             AppWindow::Context(v) => v.is_open(),
             AppWindow::Settings(v) => v.is_open(),
+            AppWindow::QueryBuilder(v) => v.is_open(),
+        }
+    }
+
+    // 窗口的 egui id，用来判断哪个窗口在最上层
+    pub fn id(&self) -> eframe::egui::Id {
+        match self {
+            AppWindow::Context(v) => v.id(),
+            AppWindow::Settings(v) => v.id(),
+            AppWindow::QueryBuilder(v) => v.id(),
         }
     }
 
     pub fn draw(&mut self, ctx: &eframe::egui::Context, search_query: &str) {
         match self {
-            AppWindow::Context(v) => v.draw(ctx, search_query),
+            AppWindow::Context(v) => v.draw(ctx),
             AppWindow::Settings(v) => v.draw(ctx),
+            AppWindow::QueryBuilder(v) => v.draw(ctx, search_query),
         }
     }
 }
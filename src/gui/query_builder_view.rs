@@ -0,0 +1,183 @@
+use crate::message::{AppMessage, QueryBuilderMessage};
+use crate::search::facets::FacetFilter;
+use crate::search::query_builder::{self, FilterRow, QueryClause, QueryField};
+use crossbeam_channel::Sender;
+use eframe::egui;
+use tantivy::query::Occur;
+
+pub struct QueryBuilderView {
+    open: bool,
+    sender: Sender<AppMessage>,
+    clauses: Vec<QueryClause>,
+    filters: Vec<FilterRow>,
+    // 从搜索框导入失败的原因
+    import_error: Option<String>,
+}
+
+impl QueryBuilderView {
+    pub fn new(sender: Sender<AppMessage>, search_query: &str, filters: &[FacetFilter]) -> Self {
+        let mut view = Self {
+            open: true,
+            sender,
+            clauses: Vec::new(),
+            filters: query_builder::filter_rows(filters),
+            import_error: None,
+        };
+        view.import(search_query);
+        if view.clauses.is_empty() {
+            view.clauses.push(QueryClause::default());
+        }
+        view
+    }
+
+    pub fn is_open(&self) -> &bool {
+        &self.open
+    }
+
+    fn import(&mut self, search_query: &str) {
+        match query_builder::from_query_string(search_query) {
+            Ok(clauses) => {
+                self.clauses = clauses;
+                self.import_error = None;
+            }
+            Err(e) => self.import_error = Some(e),
+        }
+    }
+
+    fn apply(&self, run: bool) {
+        self.sender
+            .send(AppMessage::QueryBuilder(QueryBuilderMessage::Apply {
+                query: query_builder::to_query_string(&self.clauses),
+                filters: query_builder::to_filters(&self.filters),
+                run,
+            }))
+            .unwrap();
+    }
+
+    pub fn id(&self) -> egui::Id {
+        egui::Id::new("query_builder_window")
+    }
+
+    pub fn draw(&mut self, ctx: &egui::Context, search_query: &str) {
+        let mut is_open = self.open;
+        egui::Window::new("高级搜索")
+            .id(self.id())
+            .open(&mut is_open)
+            .default_size([520.0, 320.0])
+            .show(ctx, |ui| {
+                let mut removed = None;
+                egui::Grid::new("query_builder_grid")
+                    .num_columns(6)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, clause) in self.clauses.iter_mut().enumerate() {
+                            egui::ComboBox::from_id_source(("occur", i))
+                                .selected_text(occur_label(clause.occur))
+                                .show_ui(ui, |ui| {
+                                    for occur in [Occur::Must, Occur::Should, Occur::MustNot] {
+                                        ui.selectable_value(
+                                            &mut clause.occur,
+                                            occur,
+                                            occur_label(occur),
+                                        );
+                                    }
+                                });
+                            egui::ComboBox::from_id_source(("field", i))
+                                .selected_text(clause.field.label())
+                                .show_ui(ui, |ui| {
+                                    for field in QueryField::ALL {
+                                        ui.selectable_value(
+                                            &mut clause.field,
+                                            field,
+                                            field.label(),
+                                        );
+                                    }
+                                });
+                            ui.add(
+                                egui::TextEdit::singleline(&mut clause.text)
+                                    .hint_text("关键词")
+                                    .desired_width(180.0),
+                            );
+                            ui.checkbox(&mut clause.phrase, "短语");
+                            ui.add_enabled(
+                                clause.phrase,
+                                egui::DragValue::new(&mut clause.slop)
+                                    .clamp_range(0..=20)
+                                    .prefix("间距 "),
+                            );
+                            if ui.small_button("✖").clicked() {
+                                removed = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(i) = removed {
+                    self.clauses.remove(i);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("添加条件").clicked() {
+                        self.clauses.push(QueryClause::default());
+                    }
+                    if ui.button("从搜索框读取").clicked() {
+                        self.import(search_query);
+                    }
+                });
+                if let Some(error) = &self.import_error {
+                    ui.label(egui::RichText::new(error).small().color(egui::Color32::RED));
+                }
+
+                ui.separator();
+                ui.label("筛选:");
+                egui::Grid::new("query_builder_filters")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for row in &mut self.filters {
+                            ui.label(row.field.label());
+                            let hint = row.hint();
+                            ui.add(
+                                egui::TextEdit::singleline(&mut row.value)
+                                    .hint_text(hint)
+                                    .desired_width(180.0),
+                            );
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                let generated = query_builder::to_query_string(&self.clauses);
+                ui.label("生成的查询语句:");
+                ui.label(egui::RichText::new(&generated).monospace());
+                let filters = query_builder::to_filters(&self.filters);
+                if !filters.is_empty() {
+                    let labels: Vec<String> = filters.iter().map(FacetFilter::label).collect();
+                    ui.label(
+                        egui::RichText::new(format!("筛选: {}", labels.join("，")))
+                            .color(egui::Color32::GRAY),
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    let enabled = !generated.is_empty();
+                    if ui
+                        .add_enabled(enabled, egui::Button::new("填入搜索框"))
+                        .clicked()
+                    {
+                        self.apply(false);
+                    }
+                    if ui.add_enabled(enabled, egui::Button::new("搜索")).clicked() {
+                        self.apply(true);
+                    }
+                });
+            });
+        self.open = is_open;
+    }
+}
+
+fn occur_label(occur: Occur) -> &'static str {
+    match occur {
+        Occur::Must => "必须包含",
+        Occur::Should => "可以包含",
+        Occur::MustNot => "不包含",
+    }
+}
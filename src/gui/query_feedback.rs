@@ -0,0 +1,83 @@
+use crate::search::diagnostics::QueryIssue;
+use eframe::egui;
+use eframe::egui::text::LayoutJob;
+
+// 显示在搜索框下方的查询语句问题
+pub struct QueryFeedback {
+    query: String,
+    issues: Vec<QueryIssue>,
+    // true: 宽松模式下被忽略的部分；false: 语法错误，搜索未执行
+    ignored: bool,
+}
+
+impl QueryFeedback {
+    pub fn error(query: String, issues: Vec<QueryIssue>) -> Self {
+        Self {
+            query,
+            issues,
+            ignored: false,
+        }
+    }
+
+    pub fn ignored(query: String, issues: Vec<QueryIssue>) -> Self {
+        Self {
+            query,
+            issues,
+            ignored: true,
+        }
+    }
+
+    pub fn draw(&self, ui: &mut egui::Ui) {
+        let color = if self.ignored {
+            egui::Color32::from_rgb(230, 140, 0)
+        } else {
+            egui::Color32::RED
+        };
+        let title = if self.ignored {
+            "以下部分已被忽略:"
+        } else {
+            "查询语法错误:"
+        };
+        ui.label(egui::RichText::new(title).color(color).small());
+
+        for issue in &self.issues {
+            if let Some(span) = &issue.span {
+                ui.label(self.underlined(span.clone(), color, ui));
+            }
+            ui.label(
+                egui::RichText::new(format!("  {}", issue.message))
+                    .small()
+                    .color(color),
+            );
+        }
+    }
+
+    // The query with the offending part underlined.
+    fn underlined(
+        &self,
+        span: std::ops::Range<usize>,
+        color: egui::Color32,
+        ui: &egui::Ui,
+    ) -> LayoutJob {
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let normal = egui::TextFormat {
+            font_id: font_id.clone(),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+        let marked = egui::TextFormat {
+            font_id,
+            color,
+            underline: egui::Stroke::new(1.5, color),
+            ..Default::default()
+        };
+
+        let mut job = LayoutJob::default();
+        let start = span.start.min(self.query.len());
+        let end = span.end.clamp(start, self.query.len());
+        job.append(&self.query[..start], 0.0, normal.clone());
+        job.append(&self.query[start..end], 0.0, marked);
+        job.append(&self.query[end..], 0.0, normal);
+        job
+    }
+}
@@ -0,0 +1,449 @@
+use crate::config::{GroupBy, SortBy};
+use crate::search::grouping::{self, ResultGroup};
+use crate::search::highlight::HighlightedText;
+use crate::search::query::SearchResult;
+use eframe::egui;
+use eframe::egui::text::LayoutJob;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+// 只有一条摘要时最多显示几行；多条摘要时每条一行。
+// 结果行等高，分组标题行等高，只绘制可见的行
+const SINGLE_SNIPPET_ROWS: usize = 2;
+const PADDING: f32 = 4.0;
+
+pub enum ResultEvent {
+    Select(usize),
+    // Ctrl+点击：加入或移出多选
+    Toggle(usize),
+    // Shift+点击：从当前项到此项的整段加入多选
+    Extend(usize),
+    OpenFile(usize),
+    OpenFolder(usize),
+    OpenContext(usize),
+    CopyPath(usize),
+}
+
+// current 为预览和快捷键操作的对象，marked 为复制、移动、打包的文件
+#[derive(Default)]
+pub struct Selection {
+    pub current: Option<usize>,
+    pub marked: BTreeSet<usize>,
+}
+
+impl Selection {
+    pub fn clear(&mut self) {
+        self.current = None;
+        self.marked.clear();
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.current = Some(index);
+        self.marked = BTreeSet::from([index]);
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        self.current = Some(index);
+        if !self.marked.remove(&index) {
+            self.marked.insert(index);
+        }
+    }
+
+    // 流式搜索的新一批排名会整体替换结果列表，按路径把选中项换到新的下标，
+    // 保证批量操作作用于用户选中的文件；不在新列表中的选中项丢弃
+    pub fn remap(&mut self, old: &[SearchResult], new: &[SearchResult]) {
+        if self.current.is_none() && self.marked.is_empty() {
+            return;
+        }
+        let positions: HashMap<&str, usize> = new
+            .iter()
+            .enumerate()
+            .map(|(i, result)| (result.path.as_str(), i))
+            .collect();
+        let map = |i: usize| {
+            old.get(i)
+                .and_then(|result| positions.get(result.path.as_str()).copied())
+        };
+        self.current = self.current.and_then(map);
+        self.marked = self.marked.iter().filter_map(|&i| map(i)).collect();
+    }
+}
+
+enum Row {
+    // 分组标题，值为 groups 的下标
+    Header(usize),
+    Result(usize),
+}
+
+// 只绘制可见结果的列表，上万条结果也能流畅滚动
+pub struct ResultList {
+    group_by: GroupBy,
+    sort_by: SortBy,
+    groups: Vec<ResultGroup>,
+    // 已折叠的分组
+    collapsed: HashSet<String>,
+    rows: Vec<Row>,
+    // 每行顶部的位置，末尾多一项为总高度
+    offsets: Vec<f32>,
+    row_heights: (f32, f32),
+    // 结果或分组变化后需要重建 rows
+    dirty: bool,
+    // 上一帧可见的纵向范围，用于判断选中项是否需要滚动到视野内
+    viewport: egui::Rangef,
+}
+
+impl Default for ResultList {
+    fn default() -> Self {
+        Self {
+            group_by: GroupBy::None,
+            sort_by: SortBy::Relevance,
+            groups: Vec::new(),
+            collapsed: HashSet::new(),
+            rows: Vec::new(),
+            offsets: vec![0.0],
+            row_heights: (0.0, 0.0),
+            dirty: true,
+            viewport: egui::Rangef::NOTHING,
+        }
+    }
+}
+
+impl ResultList {
+    // 搜索结果被替换后调用
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    // 按显示顺序排列的结果下标，不含折叠分组中的结果
+    pub fn visible(&self) -> Vec<usize> {
+        self.rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::Result(i) => Some(*i),
+                Row::Header(_) => None,
+            })
+            .collect()
+    }
+
+    // 按当前显示顺序（跳过折叠的分组）取选中项的下一条或上一条
+    pub fn neighbor(&self, selected: Option<usize>, forward: bool) -> Option<usize> {
+        let visible = self.visible();
+        let position = selected.and_then(|s| visible.iter().position(|&i| i == s));
+        match position {
+            None => visible.first().copied(),
+            Some(p) if forward => visible.get(p + 1).or(visible.last()).copied(),
+            Some(p) => visible.get(p.saturating_sub(1)).copied(),
+        }
+    }
+
+    // 显示顺序中 from 与 to 之间（含两端）的结果，from 不可见时只含 to
+    pub fn range(&self, from: Option<usize>, to: usize) -> Vec<usize> {
+        let visible = self.visible();
+        let Some(end) = visible.iter().position(|&i| i == to) else {
+            return Vec::new();
+        };
+        let start = from
+            .and_then(|f| visible.iter().position(|&i| i == f))
+            .unwrap_or(end);
+        visible[start.min(end)..=start.max(end)].to_vec()
+    }
+
+    fn rebuild(
+        &mut self,
+        results: &[SearchResult],
+        group_by: GroupBy,
+        sort_by: SortBy,
+        heights: (f32, f32),
+    ) {
+        if group_by != self.group_by {
+            self.collapsed.clear();
+        }
+        self.group_by = group_by;
+        self.sort_by = sort_by;
+        self.row_heights = heights;
+        self.dirty = false;
+        let order = grouping::sort_order(results, sort_by);
+        self.groups = if group_by == GroupBy::None {
+            Vec::new()
+        } else {
+            grouping::group_results(results, &order, group_by)
+        };
+
+        self.rows.clear();
+        if self.groups.is_empty() {
+            self.rows.extend(order.into_iter().map(Row::Result));
+        } else {
+            for (g, group) in self.groups.iter().enumerate() {
+                self.rows.push(Row::Header(g));
+                if !self.collapsed.contains(&group.key) {
+                    self.rows
+                        .extend(group.results.iter().map(|&i| Row::Result(i)));
+                }
+            }
+        }
+
+        let (header_height, result_height) = heights;
+        self.offsets.clear();
+        let mut y = 0.0;
+        for row in &self.rows {
+            self.offsets.push(y);
+            y += match row {
+                Row::Header(_) => header_height,
+                Row::Result(_) => result_height,
+            };
+        }
+        self.offsets.push(y);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        results: &[SearchResult],
+        group_by: GroupBy,
+        sort_by: SortBy,
+        selection: &Selection,
+        scroll_to_selected: bool,
+        searching: bool,
+        snippets_per_result: usize,
+    ) -> Option<ResultEvent> {
+        let snippets_per_result = snippets_per_result.max(1);
+        let rows_per_snippet = if snippets_per_result == 1 {
+            SINGLE_SNIPPET_ROWS
+        } else {
+            1
+        };
+        let spacing = ui.spacing().item_spacing.y;
+        let body_height = ui.text_style_height(&egui::TextStyle::Body);
+        let small_height = ui.text_style_height(&egui::TextStyle::Small);
+        let header_height = body_height + PADDING * 2.0 + spacing;
+        let result_height = body_height
+            + small_height * (snippets_per_result * rows_per_snippet) as f32
+            + PADDING * (2.0 + snippets_per_result as f32)
+            + spacing;
+
+        let heights = (header_height, result_height);
+        if self.dirty
+            || group_by != self.group_by
+            || sort_by != self.sort_by
+            || heights != self.row_heights
+        {
+            self.rebuild(results, group_by, sort_by, heights);
+        }
+
+        let mut scroll_area = egui::ScrollArea::vertical().auto_shrink([false, false]);
+        let selected_row = selection.current.and_then(|s| {
+            self.rows
+                .iter()
+                .position(|row| matches!(row, Row::Result(i) if *i == s))
+        });
+        if let Some(row) = selected_row.filter(|_| scroll_to_selected) {
+            let (top, bottom) = (self.offsets[row], self.offsets[row + 1]);
+            // 选中项在上方时对齐到顶部，在下方时对齐到底部
+            if top < self.viewport.min {
+                scroll_area = scroll_area.vertical_scroll_offset(top);
+            } else if bottom > self.viewport.max {
+                let height = self.viewport.max - self.viewport.min;
+                scroll_area = scroll_area.vertical_scroll_offset((bottom - height).max(0.0));
+            }
+        }
+
+        let mut event = None;
+        let mut toggled = None;
+        scroll_area.show_viewport(ui, |ui, viewport| {
+            self.viewport = viewport.y_range();
+            let total_height = self.offsets.last().copied().unwrap_or_default();
+            ui.set_height(total_height);
+            let origin = ui.min_rect().min;
+            let width = ui.available_width();
+
+            let first = self
+                .offsets
+                .partition_point(|&y| y <= viewport.min.y)
+                .saturating_sub(1);
+            for (row_index, row) in self.rows.iter().enumerate().skip(first) {
+                let top = self.offsets[row_index];
+                if top > viewport.max.y {
+                    break;
+                }
+                let height = self.offsets[row_index + 1] - top - spacing;
+                let rect = egui::Rect::from_min_size(
+                    origin + egui::vec2(0.0, top),
+                    egui::vec2(width, height),
+                );
+                match row {
+                    Row::Header(g) => {
+                        let group = &self.groups[*g];
+                        let collapsed = self.collapsed.contains(&group.key);
+                        if show_header(ui, rect, group, collapsed).clicked() {
+                            toggled = Some(group.key.clone());
+                        }
+                    }
+                    Row::Result(i) => {
+                        let row_event = show_result(
+                            ui,
+                            rect,
+                            *i,
+                            &results[*i],
+                            selection.current == Some(*i),
+                            selection.marked.contains(i),
+                            searching,
+                            snippets_per_result,
+                            rows_per_snippet,
+                        );
+                        if row_event.is_some() {
+                            event = row_event;
+                        }
+                    }
+                }
+            }
+        });
+
+        if let Some(key) = toggled {
+            if !self.collapsed.remove(&key) {
+                self.collapsed.insert(key);
+            }
+            self.dirty = true;
+        }
+        event
+    }
+}
+
+fn show_header(
+    ui: &mut egui::Ui,
+    rect: egui::Rect,
+    group: &ResultGroup,
+    collapsed: bool,
+) -> egui::Response {
+    let response = ui.interact(
+        rect,
+        ui.id().with(("group", &group.key)),
+        egui::Sense::click(),
+    );
+    if response.hovered() {
+        ui.painter().rect_filled(
+            rect,
+            ui.visuals().widgets.hovered.rounding,
+            ui.visuals().widgets.hovered.weak_bg_fill,
+        );
+    }
+    let inner = rect.shrink(PADDING);
+    let mut child = ui.child_ui(inner, egui::Layout::left_to_right(egui::Align::Center));
+    child.set_clip_rect(inner.intersect(ui.clip_rect()));
+    child.style_mut().interaction.selectable_labels = false;
+    child.label(if collapsed { "▶" } else { "▼" });
+    child.label(egui::RichText::new(&group.key).strong());
+    child.label(
+        egui::RichText::new(format!("({})", group.results.len())).color(egui::Color32::GRAY),
+    );
+    response
+}
+
+#[allow(clippy::too_many_arguments)]
+fn show_result(
+    ui: &mut egui::Ui,
+    rect: egui::Rect,
+    index: usize,
+    result: &SearchResult,
+    current: bool,
+    marked: bool,
+    searching: bool,
+    snippets_per_result: usize,
+    rows_per_snippet: usize,
+) -> Option<ResultEvent> {
+    let response = ui.interact(rect, ui.id().with(("result", index)), egui::Sense::click());
+
+    let visuals = ui.visuals();
+    let fill = if current {
+        visuals.selection.bg_fill.linear_multiply(0.3)
+    } else if marked {
+        visuals.selection.bg_fill.linear_multiply(0.15)
+    } else if response.hovered() {
+        visuals.widgets.hovered.weak_bg_fill
+    } else {
+        visuals.faint_bg_color
+    };
+    ui.painter().rect(
+        rect,
+        visuals.widgets.noninteractive.rounding,
+        fill,
+        visuals.widgets.noninteractive.bg_stroke,
+    );
+
+    let inner = rect.shrink(PADDING);
+    let mut child = ui.child_ui(inner, egui::Layout::top_down(egui::Align::Min));
+    child.set_clip_rect(inner.intersect(ui.clip_rect()));
+    // 文字不可选中，点击交给整行
+    child.style_mut().interaction.selectable_labels = false;
+    child.horizontal(|ui| {
+        ui.add(egui::Label::new(egui::RichText::new(&result.path).strong()).truncate(true));
+        ui.label(
+            egui::RichText::new(format!("{:.2}", result.score))
+                .small()
+                .color(egui::Color32::GRAY),
+        );
+    });
+    if result.snippets.is_empty() && searching {
+        // 摘要尚在生成中
+        child.label(egui::RichText::new("…").small().color(egui::Color32::GRAY));
+    }
+    for snippet in result.snippets.iter().take(snippets_per_result) {
+        child.label(snippet_job(&child, snippet, rows_per_snippet));
+    }
+
+    let mut event = None;
+    if response.clicked() {
+        let modifiers = ui.input(|i| i.modifiers);
+        event = Some(if modifiers.command {
+            ResultEvent::Toggle(index)
+        } else if modifiers.shift {
+            ResultEvent::Extend(index)
+        } else {
+            ResultEvent::Select(index)
+        });
+    }
+    if response.double_clicked() {
+        event = Some(ResultEvent::OpenFile(index));
+    }
+    response.context_menu(|ui| {
+        if ui.button("打开文件").clicked() {
+            event = Some(ResultEvent::OpenFile(index));
+            ui.close_menu();
+        }
+        if ui.button("预览关键词上下文").clicked() {
+            event = Some(ResultEvent::OpenContext(index));
+            ui.close_menu();
+        }
+        if ui.button("打开文件所在文件夹").clicked() {
+            event = Some(ResultEvent::OpenFolder(index));
+            ui.close_menu();
+        }
+        if ui.button("复制路径").clicked() {
+            event = Some(ResultEvent::CopyPath(index));
+            ui.close_menu();
+        }
+    });
+    event
+}
+
+fn snippet_job(ui: &egui::Ui, snippet: &HighlightedText, max_rows: usize) -> LayoutJob {
+    let normal = egui::TextFormat {
+        font_id: egui::TextStyle::Small.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let highlighted = egui::TextFormat {
+        color: egui::Color32::RED,
+        ..normal.clone()
+    };
+
+    let mut job = LayoutJob::default();
+    job.wrap.max_rows = max_rows;
+    let mut cursor = 0;
+    for range in &snippet.highlights {
+        job.append(&snippet.text[cursor..range.start], 0.0, normal.clone());
+        job.append(&snippet.text[range.clone()], 0.0, highlighted.clone());
+        cursor = range.end;
+    }
+    job.append(&snippet.text[cursor..], 0.0, normal);
+    job
+}
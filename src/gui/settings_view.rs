@@ -1,21 +1,54 @@
 use crate::message::{AppMessage, SettingsMessage};
-use crate::config::Theme;
+use crate::config::{AnalyzerKind, AppSettings, ShortcutAction, Theme};
 use crossbeam_channel::Sender;
 use eframe::egui;
+use std::collections::BTreeMap;
 
 pub struct SettingsView {
     open: bool,
     sender: Sender<AppMessage>,
     // Local state for the view
     theme: Theme,
+    analyzer: AnalyzerKind,
+    user_dict: String,
+    stopwords: String,
+    synonyms: String,
+    snippet_length: usize,
+    snippets_per_result: usize,
+    // 词典编辑后尚未应用
+    dictionary_dirty: bool,
+    shortcuts: BTreeMap<ShortcutAction, egui::KeyboardShortcut>,
+    // 正在等待新按键的操作
+    recording: Option<ShortcutAction>,
+    api_enabled: bool,
+    api_port: u16,
+    api_token: String,
+    // 接口设置编辑后尚未应用
+    api_dirty: bool,
 }
 
 impl SettingsView {
-    pub fn new(sender: Sender<AppMessage>, initial_theme: Theme) -> Self {
+    pub fn new(sender: Sender<AppMessage>, settings: &AppSettings) -> Self {
         Self {
             open: true,
             sender,
-            theme: initial_theme,
+            theme: settings.theme,
+            analyzer: settings.analyzer,
+            user_dict: settings.user_dict.clone(),
+            stopwords: settings.stopwords.clone(),
+            synonyms: settings.synonyms.clone(),
+            snippet_length: settings.snippet_length,
+            snippets_per_result: settings.snippets_per_result,
+            dictionary_dirty: false,
+            shortcuts: ShortcutAction::ALL
+                .iter()
+                .map(|&action| (action, settings.shortcut(action)))
+                .collect(),
+            recording: None,
+            api_enabled: settings.api_enabled,
+            api_port: settings.api_port,
+            api_token: settings.api_token.clone(),
+            api_dirty: false,
         }
     }
 
@@ -23,9 +56,15 @@ impl SettingsView {
         &self.open
     }
 
+    pub fn id(&self) -> egui::Id {
+        egui::Id::new("settings_window")
+    }
+
     pub fn draw(&mut self, ctx: &egui::Context) {
+        let mut is_open = self.open;
         egui::Window::new("Settings")
-            .open(&mut self.open)
+            .id(self.id())
+            .open(&mut is_open)
             .default_size([300.0, 400.0])
             .show(ctx, |ui| {
                 egui::ScrollArea::vertical().show(ui, |ui| {
@@ -59,9 +98,310 @@ impl SettingsView {
                         }
                     });
 
+                    ui.separator();
+
+                    ui.heading("分词设置");
+                    ui.separator();
+
+                    let mut normalize = self.analyzer == AnalyzerKind::JiebaNormalized;
+                    if ui
+                        .checkbox(&mut normalize, "繁简转换 / 全半角折叠 / 忽略大小写")
+                        .changed()
+                    {
+                        self.analyzer = if normalize {
+                            AnalyzerKind::JiebaNormalized
+                        } else {
+                            AnalyzerKind::Jieba
+                        };
+                        self.sender
+                            .send(AppMessage::Settings(SettingsMessage::AnalyzerChanged(
+                                self.analyzer,
+                            )))
+                            .unwrap();
+                    }
+                    ui.label(
+                        egui::RichText::new("修改后需重新索引才能生效")
+                            .small()
+                            .color(egui::Color32::GRAY),
+                    );
+
+                    ui.separator();
+
+                    ui.heading("词典设置");
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.label("用户词典 (每行: 词语 [词频] [词性])");
+                        if ui.button("从文件加载...").clicked() {
+                            if let Some(content) = pick_text_file() {
+                                self.user_dict = content;
+                                self.dictionary_dirty = true;
+                            }
+                        }
+                    });
+                    if ui
+                        .add(
+                            egui::TextEdit::multiline(&mut self.user_dict)
+                                .desired_rows(6)
+                                .desired_width(f32::INFINITY),
+                        )
+                        .changed()
+                    {
+                        self.dictionary_dirty = true;
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("停用词 (每行一个)");
+                        if ui.button("从文件加载...").clicked() {
+                            if let Some(content) = pick_text_file() {
+                                self.stopwords = content;
+                                self.dictionary_dirty = true;
+                            }
+                        }
+                    });
+                    if ui
+                        .add(
+                            egui::TextEdit::multiline(&mut self.stopwords)
+                                .desired_rows(6)
+                                .desired_width(f32::INFINITY),
+                        )
+                        .changed()
+                    {
+                        self.dictionary_dirty = true;
+                    }
+
+                    if ui
+                        .add_enabled(self.dictionary_dirty, egui::Button::new("应用"))
+                        .clicked()
+                    {
+                        self.dictionary_dirty = false;
+                        self.sender
+                            .send(AppMessage::Settings(SettingsMessage::DictionaryChanged {
+                                user_dict: self.user_dict.clone(),
+                                stopwords: self.stopwords.clone(),
+                            }))
+                            .unwrap();
+                    }
+                    ui.label(
+                        egui::RichText::new("修改后需重新索引才能生效")
+                            .small()
+                            .color(egui::Color32::GRAY),
+                    );
+
+                    ui.separator();
+
+                    ui.heading("同义词");
+                    ui.separator();
+
+                    ui.label("每行一组同义词，用空格或逗号分隔，例如: 发票 票据");
+                    if ui
+                        .add(
+                            egui::TextEdit::multiline(&mut self.synonyms)
+                                .desired_rows(6)
+                                .desired_width(f32::INFINITY),
+                        )
+                        .changed()
+                    {
+                        self.sender
+                            .send(AppMessage::Settings(SettingsMessage::SynonymsChanged(
+                                self.synonyms.clone(),
+                            )))
+                            .unwrap();
+                    }
+
+                    ui.separator();
+
+                    ui.heading("结果摘要");
+                    ui.separator();
+
+                    let mut changed = false;
+                    ui.horizontal(|ui| {
+                        ui.label("摘要长度:");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.snippet_length)
+                                    .clamp_range(40..=500)
+                                    .suffix(" 字"),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("每个结果的摘要数:");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.snippets_per_result)
+                                    .clamp_range(1..=5),
+                            )
+                            .changed();
+                    });
+                    if changed {
+                        self.sender
+                            .send(AppMessage::Settings(SettingsMessage::SnippetsChanged {
+                                length: self.snippet_length,
+                                count: self.snippets_per_result,
+                            }))
+                            .unwrap();
+                    }
+                    ui.label(
+                        egui::RichText::new("下次搜索时生效")
+                            .small()
+                            .color(egui::Color32::GRAY),
+                    );
+
+                    ui.separator();
+
+                    ui.heading("快捷键");
+                    ui.separator();
+                    self.draw_shortcuts(ui);
+
+                    ui.separator();
+
+                    ui.heading("本地 API");
+                    ui.separator();
+                    self.draw_api(ui);
+
                     ui.separator();
                     // Add other settings here in the future
                 });
             });
+        self.open = is_open;
+    }
+}
+
+impl SettingsView {
+    fn draw_shortcuts(&mut self, ui: &mut egui::Ui) {
+        if let Some(action) = self.recording {
+            let pressed = ui.input(|i| {
+                i.events.iter().find_map(|e| match e {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+            if let Some((key, modifiers)) = pressed {
+                // 录制用掉的按键不再触发对应的操作
+                ui.input_mut(|i| i.consume_key(modifiers, key));
+                self.recording = None;
+                if key != egui::Key::Escape {
+                    let shortcut = egui::KeyboardShortcut::new(modifiers, key);
+                    self.shortcuts.insert(action, shortcut);
+                    self.sender
+                        .send(AppMessage::Settings(SettingsMessage::ShortcutChanged(
+                            action, shortcut,
+                        )))
+                        .unwrap();
+                }
+            }
+        }
+
+        egui::Grid::new("shortcut_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for action in ShortcutAction::ALL {
+                    let shortcut = self.shortcuts[&action];
+                    ui.label(action.label());
+                    if self.recording == Some(action) {
+                        ui.label(
+                            egui::RichText::new("请按下新的快捷键 (Esc 取消)")
+                                .color(egui::Color32::from_rgb(230, 140, 0)),
+                        );
+                        if ui.small_button("取消").clicked() {
+                            self.recording = None;
+                        }
+                    } else {
+                        let conflict = self
+                            .shortcuts
+                            .iter()
+                            .any(|(other, s)| *other != action && *s == shortcut);
+                        let mut text = egui::RichText::new(ui.ctx().format_shortcut(&shortcut));
+                        if conflict {
+                            text = text.color(egui::Color32::RED);
+                        }
+                        let response = ui.label(text);
+                        if conflict {
+                            response.on_hover_text("与其他操作的快捷键冲突");
+                        }
+                        if ui.small_button("修改").clicked() {
+                            self.recording = Some(action);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if ui.button("全部恢复默认").clicked() {
+            self.recording = None;
+            self.shortcuts = ShortcutAction::ALL
+                .iter()
+                .map(|&action| (action, action.default_shortcut()))
+                .collect();
+            self.sender
+                .send(AppMessage::Settings(SettingsMessage::ShortcutsReset))
+                .unwrap();
+        }
+    }
+}
+
+impl SettingsView {
+    fn draw_api(&mut self, ui: &mut egui::Ui) {
+        self.api_dirty |= ui
+            .checkbox(&mut self.api_enabled, "启用本地 HTTP 接口")
+            .changed();
+        ui.add_enabled_ui(self.api_enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("端口:");
+                self.api_dirty |= ui
+                    .add(egui::DragValue::new(&mut self.api_port).clamp_range(1024..=65535))
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Token:");
+                self.api_dirty |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.api_token)
+                            .password(true)
+                            .hint_text("留空则不校验"),
+                    )
+                    .changed();
+            });
+        });
+        if ui
+            .add_enabled(self.api_dirty, egui::Button::new("应用"))
+            .clicked()
+        {
+            self.api_dirty = false;
+            self.sender
+                .send(AppMessage::Settings(SettingsMessage::ApiChanged {
+                    enabled: self.api_enabled,
+                    port: self.api_port,
+                    token: self.api_token.trim().to_string(),
+                }))
+                .unwrap();
+        }
+        ui.label(
+            egui::RichText::new(
+                "只监听 127.0.0.1，浏览器打开根路径即为网页版，接口有 /search、/document、/stats、/reindex；\n设置了 token 时请求需带 Authorization: Bearer <token> 头或 token 参数",
+            )
+            .small()
+            .color(egui::Color32::GRAY),
+        );
+    }
+}
+
+fn pick_text_file() -> Option<String> {
+    let path = rfd::FileDialog::new()
+        .add_filter("文本文件", &["txt", "dict"])
+        .pick_file()?;
+    match std::fs::read_to_string(&path) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            eprintln!("Failed to read {:?}: {}", path, e);
+            None
+        }
     }
 }
@@ -0,0 +1,132 @@
+use crate::config::{GroupBy, SortBy};
+use crate::search::facets::FacetFilter;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_HISTORY: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    pub query: String,
+    // Unix 时间戳（秒）
+    pub time: i64,
+    pub results: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    // 侧栏中的筛选条件，其中的文件夹条件就是搜索范围
+    pub filters: Vec<FacetFilter>,
+    pub group_by: GroupBy,
+    pub sort_by: SortBy,
+    // 固定在左侧面板
+    pub pinned: bool,
+}
+
+// 随应用状态一起保存
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SearchHistory {
+    // 最近的在前
+    pub entries: Vec<HistoryEntry>,
+    pub saved: Vec<SavedSearch>,
+}
+
+impl SearchHistory {
+    // 只记录明确提交的搜索；重复的查询移到最前
+    pub fn record(&mut self, query: &str, results: usize) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e.query != query);
+        self.entries.insert(
+            0,
+            HistoryEntry {
+                query: query.to_string(),
+                time: now(),
+                results,
+            },
+        );
+        self.entries.truncate(MAX_HISTORY);
+    }
+
+    // 同名的已保存搜索会被覆盖，保留原来的固定状态
+    pub fn save(&mut self, mut search: SavedSearch) {
+        match self.saved.iter_mut().find(|s| s.name == search.name) {
+            Some(existing) => {
+                search.pinned = existing.pinned;
+                *existing = search;
+            }
+            None => self.saved.push(search),
+        }
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+// "刚刚"、"5 分钟前"……超过一周显示日期
+pub fn relative_time(time: i64) -> String {
+    let elapsed = now() - time;
+    match elapsed {
+        ..=59 => "刚刚".to_string(),
+        60..=3599 => format!("{} 分钟前", elapsed / 60),
+        3600..=86399 => format!("{} 小时前", elapsed / 3600),
+        86400..=604799 => format!("{} 天前", elapsed / 86400),
+        _ => {
+            let date = tantivy::DateTime::from_timestamp_secs(time).into_utc();
+            format!(
+                "{}-{:02}-{:02}",
+                date.year(),
+                date.month() as u8,
+                date.day()
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queries(history: &SearchHistory) -> Vec<&str> {
+        history.entries.iter().map(|e| e.query.as_str()).collect()
+    }
+
+    #[test]
+    fn record_keeps_consecutive_searches_that_extend_each_other() {
+        let mut history = SearchHistory::default();
+        history.record("合同", 3);
+        history.record("合同 2024", 1);
+        assert_eq!(queries(&history), ["合同 2024", "合同"]);
+        assert_eq!(history.entries[1].results, 3);
+    }
+
+    #[test]
+    fn record_moves_repeated_queries_to_the_front() {
+        let mut history = SearchHistory::default();
+        history.record("合同", 3);
+        history.record("报告", 2);
+        history.record(" 合同 ", 5);
+        assert_eq!(queries(&history), ["合同", "报告"]);
+        assert_eq!(history.entries[0].results, 5);
+    }
+
+    #[test]
+    fn record_skips_empty_queries_and_keeps_the_limit() {
+        let mut history = SearchHistory::default();
+        history.record("  ", 0);
+        assert!(history.entries.is_empty());
+        for i in 0..MAX_HISTORY + 5 {
+            history.record(&i.to_string(), i);
+        }
+        assert_eq!(history.entries.len(), MAX_HISTORY);
+        assert_eq!(history.entries[0].query, (MAX_HISTORY + 4).to_string());
+    }
+}
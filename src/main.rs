@@ -1,28 +1,53 @@
 #![windows_subsystem = "windows"]
 
 // Re-organize use statements for clarity
+use crate::api::ApiServer;
 use crate::app_state::AppState;
-use crate::config::AppSettings;
+use crate::config::{AppSettings, GroupBy, ShortcutAction, SortBy};
+use crate::gui::batch_bar::BatchEvent;
+use crate::gui::context_view::ContextView;
+use crate::gui::facet_panel::FacetEvent;
+use crate::gui::history_menu::{HistoryEvent, HistoryMenu};
+use crate::gui::query_builder_view::QueryBuilderView;
+use crate::gui::query_feedback::QueryFeedback;
+use crate::gui::result_list::{ResultEvent, ResultList, Selection};
 use crate::gui::settings_view::SettingsView;
 use crate::gui::AppWindow;
-use crate::message::{AppMessage, IndexMessage, SearchMessage, SettingsMessage};
-use crate::search::query::SearchResult;
+use crate::message::{
+    AppMessage, ExportMessage, FileOpMessage, IndexMessage, PreviewMessage, QueryBuilderMessage,
+    SearchMessage, SettingsMessage,
+};
+use crate::history::{SavedSearch, SearchHistory};
+use crate::search::engine;
+use crate::search::facets::{FacetFilter, FacetSummary};
+use crate::search::query::{SearchRequest, SearchResult};
+use crate::utils::export::{self, ExportFormat};
+use crate::utils::file_ops::{self, FileItem, FileOpStatus, FileOperation};
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use eframe::egui;
+use mytxt_core::IndexService;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Application-specific modules
+mod api;
 mod app_state;
+mod cli;
 mod config;
 mod gui;
+mod history;
 mod message;
 mod search;
 mod utils;
 
+// 输入停顿多久后触发即时搜索
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+// 搜索框的控件 id，供“聚焦搜索框”快捷键使用
+const SEARCH_INPUT_ID: &str = "search_input";
+
 // APP结构体定义
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
@@ -30,20 +55,73 @@ struct MyApp {
     // 指定索引目录
     #[serde(skip)]
     index_path: Option<PathBuf>,
-    // 指定搜索关键词
-    #[serde(skip)]
+    // 指定搜索关键词，重启后保留
     search_query: String,
     // 返回的搜索结果
     #[serde(skip)]
     search_results: Vec<SearchResult>,
     #[serde(skip)]
     search_duration: Option<Duration>,
+    // 查询语法错误或被忽略的部分
+    #[serde(skip)]
+    query_feedback: Option<QueryFeedback>,
+    #[serde(skip)]
+    search_error: Option<String>,
+    // 上一次导出的结果提示
+    #[serde(skip)]
+    export_status: Option<String>,
     #[serde(skip)]
     state: AppState,
     #[serde(skip)]
     cancellation_token: Option<Arc<AtomicBool>>,
+    // 每发起一次搜索加一，用于丢弃过期的搜索结果
+    #[serde(skip)]
+    search_generation: u64,
+    // 当前这一代搜索的查询语句
+    #[serde(skip)]
+    searching_query: String,
+    // 即时搜索的触发时间
+    #[serde(skip)]
+    search_deadline: Option<Instant>,
+    // 当前搜索由用户提交（回车、搜索按钮等），完成后记入历史；即时搜索不记录
+    #[serde(skip)]
+    record_history: bool,
+    // 分词设置改动后，已有索引与查询分词不再一致；重启后保留，直到重新索引完成
+    reindex_needed: bool,
+    // 预览面板中显示的结果和多选的文件
+    #[serde(skip)]
+    selection: Selection,
+    #[serde(skip)]
+    scroll_to_selected: bool,
+    #[serde(skip)]
+    preview: Option<ContextView>,
+    #[serde(skip)]
+    result_list: ResultList,
+    // 进行中或刚完成的复制、移动、打包
+    #[serde(skip)]
+    file_op: Option<FileOpStatus>,
+    // 当前查询的结果分布，以及在侧栏中选中的筛选条件
+    #[serde(skip)]
+    facets: Vec<FacetSummary>,
+    #[serde(skip)]
+    facet_filters: Vec<FacetFilter>,
     settings: AppSettings,
+    // 最近搜索和已保存的搜索
+    history: SearchHistory,
+    #[serde(skip)]
+    history_menu: HistoryMenu,
 
+    // 已打开的索引，搜索和索引线程各持有一份引用
+    #[serde(skip)]
+    index_service: Arc<IndexService>,
+    // 索引目录，在应用数据目录下
+    #[serde(skip)]
+    index_dir: PathBuf,
+    // 本地 HTTP 接口，与界面共用 index_service
+    #[serde(skip)]
+    api_server: Option<ApiServer>,
+    #[serde(skip)]
+    api_error: Option<String>,
     #[serde(skip)]
     sender: Sender<AppMessage>,
     #[serde(skip)]
@@ -60,9 +138,30 @@ impl Default for MyApp {
             search_query: String::new(),
             search_results: Vec::new(),
             search_duration: None,
+            query_feedback: None,
+            search_error: None,
+            export_status: None,
             state: AppState::default(),
             cancellation_token: None,
+            search_generation: 0,
+            searching_query: String::new(),
+            search_deadline: None,
+            record_history: false,
+            reindex_needed: false,
+            selection: Selection::default(),
+            scroll_to_selected: false,
+            preview: None,
+            result_list: ResultList::default(),
+            file_op: None,
+            facets: Vec::new(),
+            facet_filters: Vec::new(),
             settings: AppSettings::default(),
+            history: SearchHistory::default(),
+            history_menu: HistoryMenu::default(),
+            index_service: Arc::default(),
+            index_dir: engine::index_dir(),
+            api_server: None,
+            api_error: None,
             sender,
             receiver,
             windows: Vec::new(),
@@ -82,15 +181,512 @@ impl MyApp {
         let (sender, receiver) = unbounded();
         app.sender = sender;
         app.receiver = receiver;
+        app.restart_api_server();
 
         app
     }
+
+    // 设置窗口中的改动写回 settings，随 App 一起保存
+    fn apply_settings_message(&mut self, message: SettingsMessage) {
+        match message {
+            // 主题变更
+            SettingsMessage::ThemeChanged(theme) => {
+                self.settings.theme = theme;
+            }
+            // 分词器变更，下次索引时生效
+            SettingsMessage::AnalyzerChanged(analyzer) => {
+                self.reindex_needed |= self.settings.analyzer != analyzer;
+                self.settings.analyzer = analyzer;
+            }
+            SettingsMessage::DictionaryChanged {
+                user_dict,
+                stopwords,
+            } => {
+                self.reindex_needed |=
+                    self.settings.user_dict != user_dict || self.settings.stopwords != stopwords;
+                self.settings.user_dict = user_dict;
+                self.settings.stopwords = stopwords;
+            }
+            // 同义词在查询时展开，无需重新索引
+            SettingsMessage::SynonymsChanged(synonyms) => {
+                self.settings.synonyms = synonyms;
+            }
+            SettingsMessage::SnippetsChanged { length, count } => {
+                self.settings.snippet_length = length;
+                self.settings.snippets_per_result = count;
+            }
+            SettingsMessage::ShortcutChanged(action, shortcut) => {
+                self.settings.shortcuts.insert(action, shortcut);
+            }
+            SettingsMessage::ShortcutsReset => {
+                self.settings.shortcuts.clear();
+            }
+            SettingsMessage::ApiChanged {
+                enabled,
+                port,
+                token,
+            } => {
+                self.settings.api_enabled = enabled;
+                self.settings.api_port = port;
+                self.settings.api_token = token;
+                self.restart_api_server();
+            }
+        }
+    }
+
+    // 按当前设置（重新）启动本地接口，启动时和开关、端口、token 变化时调用
+    fn restart_api_server(&mut self) {
+        // 先停掉旧的服务，端口不变时才能重新绑定
+        self.api_server = None;
+        self.api_error = None;
+        if !self.settings.api_enabled {
+            return;
+        }
+        match ApiServer::start(
+            self.settings.api_config(),
+            self.index_service.clone(),
+            &self.index_dir,
+            Some(self.sender.clone()),
+        ) {
+            Ok(server) => self.api_server = Some(server),
+            Err(e) => {
+                eprintln!("Failed to start API server: {}", e);
+                self.api_error = Some(e.to_string());
+            }
+        }
+    }
+
+    // 用户明确提交的搜索，完成后记入历史
+    fn submit_search(&mut self) {
+        self.start_search();
+        self.record_history = true;
+    }
+
+    // 在后台线程中执行当前搜索框中的查询，正在进行的搜索会被取代
+    fn start_search(&mut self) {
+        self.cancel_search();
+        self.record_history = false;
+        self.state = AppState::Searching;
+        self.query_feedback = None;
+        self.search_error = None;
+        self.export_status = None;
+        self.searching_query = self.search_query.clone();
+        self.selection.clear();
+        self.preview = None;
+        let query = self.search_query.clone();
+        let generation = self.search_generation;
+        let mut options = self.settings.search_options();
+        options.filters = self.facet_filters.clone();
+        let analyzer_options = self.settings.analyzer_options();
+        let service = self.index_service.clone();
+        let index_dir = self.index_dir.clone();
+        let sender = self.sender.clone();
+        let token = Arc::new(AtomicBool::new(false));
+        self.cancellation_token = Some(token.clone());
+
+        // 多线程搜索
+        thread::spawn(move || {
+            let sender_clone = sender.clone();
+            let result = std::panic::catch_unwind(move || {
+                engine::search(
+                    &service,
+                    &index_dir,
+                    &analyzer_options,
+                    SearchRequest::new(query, options),
+                    generation,
+                    sender_clone,
+                    token,
+                )
+            });
+
+            match result {
+                Ok(Ok(_)) => {
+                    // Search completed successfully (sent its own message)
+                }
+                Ok(Err(e)) => {
+                    // Search returned a normal error
+                    sender
+                        .send(AppMessage::search(generation, SearchMessage::Error(e.to_string())))
+                        .unwrap();
+                }
+                Err(panic_payload) => {
+                    // Search panicked
+                    eprintln!("Search thread panicked: {:?}", panic_payload);
+                    let error_msg = "A critical error occurred in the search engine, possibly due to a corrupt file.".to_string();
+                    sender
+                        .send(AppMessage::search(generation, SearchMessage::Error(error_msg)))
+                        .unwrap();
+                }
+            }
+        });
+    }
+
+    // 停止正在进行的搜索，之后到达的旧结果都会被丢弃
+    fn cancel_search(&mut self) {
+        if let Some(token) = self.cancellation_token.take() {
+            token.store(true, Ordering::SeqCst);
+        }
+        self.search_generation += 1;
+        self.search_deadline = None;
+        if self.state == AppState::Searching {
+            self.state = AppState::Idle;
+        }
+    }
+
+    // 读取文件打开预览，供预览窗口和预览面板共用；命中位置随后由后台线程补上
+    fn load_preview(&self, ctx: &egui::Context, path: String) -> Option<ContextView> {
+        match crate::utils::file_utils::read_preview(std::path::Path::new(&path)) {
+            Ok((content, document)) => {
+                self.locate_matches(ctx, &path, &content);
+                let mut view = ContextView::new(path, content);
+                if let Some(document) = document {
+                    view = view.with_document(document);
+                }
+                Some(view)
+            }
+            Err(e) => {
+                eprintln!("Failed to read file for context view: {}", e);
+                None
+            }
+        }
+    }
+
+    // 用分词器定位命中词，和搜索结果的高亮保持一致。
+    // 长文档整篇分词较慢，放到后台线程，完成后通过 PreviewMessage 交回界面。
+    fn locate_matches(&self, ctx: &egui::Context, path: &str, content: &str) {
+        // 预览的结果来自已打开的索引，这里不会在界面线程上打开索引
+        let Some(searcher) = self.index_service.get(&self.index_dir) else {
+            return;
+        };
+        let path = path.to_string();
+        let content = content.to_string();
+        let query = self.searching_query.clone();
+        let options = self.settings.search_options();
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let matches = searcher
+                .match_ranges(&content, &query, &options)
+                .unwrap_or_else(|e| {
+                    eprintln!("Failed to locate matches: {}", e);
+                    Vec::new()
+                });
+            // 界面已退出时无人接收，直接丢弃
+            let _ = sender.send(AppMessage::Preview(PreviewMessage::Matches { path, matches }));
+            ctx.request_repaint();
+        });
+    }
+
+    // 侧栏筛选条件变化后在当前查询上重新搜索
+    fn apply_facet_event(&mut self, event: FacetEvent) {
+        match event {
+            FacetEvent::Select(filter) => {
+                // 每个维度只保留一个条件，文件夹维度向下钻取时替换上一层
+                self.facet_filters.retain(|f| f.field != filter.field);
+                self.facet_filters.push(filter);
+            }
+            FacetEvent::Remove(i) => {
+                if i < self.facet_filters.len() {
+                    self.facet_filters.remove(i);
+                }
+            }
+            FacetEvent::Clear => self.facet_filters.clear(),
+        }
+        if !self.search_query.trim().is_empty() && !matches!(self.state, AppState::Indexing { .. }) {
+            self.start_search();
+        }
+    }
+
+    fn apply_history_event(&mut self, event: HistoryEvent) {
+        let can_search = !matches!(self.state, AppState::Indexing { .. });
+        match event {
+            HistoryEvent::Run(query) => {
+                self.search_query = query;
+                self.facet_filters.clear();
+                if can_search {
+                    self.submit_search();
+                }
+            }
+            HistoryEvent::RunSaved(i) => {
+                let Some(saved) = self.history.saved.get(i).cloned() else {
+                    return;
+                };
+                self.search_query = saved.query;
+                self.facet_filters = saved.filters;
+                self.settings.group_by = saved.group_by;
+                self.settings.sort_by = saved.sort_by;
+                if can_search {
+                    self.submit_search();
+                }
+            }
+            HistoryEvent::SaveCurrent(name) => {
+                self.history.save(SavedSearch {
+                    name,
+                    query: self.search_query.trim().to_string(),
+                    filters: self.facet_filters.clone(),
+                    group_by: self.settings.group_by,
+                    sort_by: self.settings.sort_by,
+                    pinned: false,
+                });
+            }
+            HistoryEvent::TogglePin(i) => {
+                if let Some(saved) = self.history.saved.get_mut(i) {
+                    saved.pinned = !saved.pinned;
+                }
+            }
+            HistoryEvent::DeleteSaved(i) => {
+                if i < self.history.saved.len() {
+                    self.history.saved.remove(i);
+                }
+            }
+            HistoryEvent::ClearHistory => self.history.entries.clear(),
+        }
+    }
+
+    // 导出本次搜索的全部结果，而不只是列表中可见的部分
+    // 选择文件后在后台写入，逐条读取文件大小可能较慢，结果通过 ExportMessage 返回
+    fn export_results(&mut self, ctx: &egui::Context, format: ExportFormat) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(format!("搜索结果.{}", format.extension()))
+            .save_file()
+        else {
+            return;
+        };
+        self.export_status = Some("正在导出...".to_string());
+        let query = self.searching_query.clone();
+        let results = self.search_results.clone();
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let message = match export::export(&path, format, &query, &results) {
+                Ok(count) => ExportMessage::Finished(count),
+                Err(e) => ExportMessage::Error(e.to_string()),
+            };
+            let _ = sender.send(AppMessage::Export(message));
+            ctx.request_repaint();
+        });
+    }
+
+    fn select_result(&mut self, index: usize) {
+        if index < self.search_results.len() {
+            self.selection.select(index);
+            self.scroll_to_selected = true;
+        }
+    }
+
+    fn apply_batch_event(&mut self, event: BatchEvent) {
+        match event {
+            BatchEvent::Run(operation) => self.start_file_op(operation),
+            BatchEvent::SelectAll => {
+                self.selection.marked = self.result_list.visible().into_iter().collect();
+            }
+            BatchEvent::ClearSelection => self.selection.marked.clear(),
+            BatchEvent::DismissStatus => self.file_op = None,
+        }
+    }
+
+    // 选择目标后在后台处理多选的文件，进度通过 FileOpMessage 返回
+    fn start_file_op(&mut self, operation: FileOperation) {
+        let items: Vec<FileItem> = self
+            .selection
+            .marked
+            .iter()
+            .filter_map(|&i| self.search_results.get(i))
+            .map(|r| FileItem::new(&r.path, &r.root))
+            .collect();
+        if items.is_empty() {
+            return;
+        }
+        let destination = match operation {
+            FileOperation::Zip => rfd::FileDialog::new()
+                .add_filter("zip", &["zip"])
+                .set_file_name("搜索结果.zip")
+                .save_file(),
+            FileOperation::Copy | FileOperation::Move => rfd::FileDialog::new().pick_folder(),
+        };
+        let Some(destination) = destination else {
+            return;
+        };
+
+        self.file_op = Some(FileOpStatus::new(operation, items.len()));
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            if let Err(e) = file_ops::run(operation, items, &destination, sender.clone()) {
+                sender
+                    .send(AppMessage::FileOp(FileOpMessage::Error(e.to_string())))
+                    .unwrap();
+            }
+        });
+    }
+
+    // 选中结果和预览面板不一致时重新加载预览
+    fn sync_preview(&mut self, ctx: &egui::Context) {
+        let Some(result) = self
+            .selection
+            .current
+            .and_then(|i| self.search_results.get(i))
+        else {
+            return;
+        };
+        if self.preview.as_ref().map(|v| &v.path) != Some(&result.path) {
+            self.preview = self
+                .load_preview(ctx, result.path.clone())
+                .map(ContextView::docked);
+        }
+    }
+
+    fn selected_path(&self) -> Option<String> {
+        let i = self.selection.current?;
+        Some(self.search_results.get(i)?.path.clone())
+    }
+
+    // 处理全局快捷键，在绘制界面之前调用，所以优先于控件自己的按键处理
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) -> Action {
+        let typing = ctx.wants_keyboard_input();
+        // 修饰键多的先匹配，避免 Ctrl+Shift+O 被当成 Ctrl+O
+        let mut bindings: Vec<_> = ShortcutAction::ALL
+            .iter()
+            .map(|&action| (action, self.settings.shortcut(action)))
+            .collect();
+        bindings.sort_by_key(|(_, s)| {
+            let m = s.modifiers;
+            std::cmp::Reverse(m.ctrl as u8 + m.shift as u8 + m.alt as u8 + m.mac_cmd as u8)
+        });
+
+        let mut triggered = None;
+        ctx.input_mut(|i| {
+            for (action, shortcut) in &bindings {
+                // 输入文字时不拦截不带修饰键的按键（方向键等）
+                if typing
+                    && shortcut.modifiers.is_none()
+                    && shortcut.logical_key != egui::Key::Escape
+                {
+                    continue;
+                }
+                // 没有搜索在进行时把 Esc 留给菜单和弹窗
+                if *action == ShortcutAction::StopSearch && self.state != AppState::Searching {
+                    continue;
+                }
+                if i.consume_shortcut(shortcut) {
+                    triggered = Some(*action);
+                    break;
+                }
+            }
+        });
+        let Some(action) = triggered else {
+            return Action::None;
+        };
+
+        let can_search = !matches!(self.state, AppState::Indexing { .. });
+        match action {
+            ShortcutAction::FocusSearch => {
+                ctx.memory_mut(|m| m.request_focus(egui::Id::new(SEARCH_INPUT_ID)));
+            }
+            ShortcutAction::RunSearch => {
+                if can_search && !self.search_query.trim().is_empty() {
+                    self.submit_search();
+                }
+            }
+            ShortcutAction::StopSearch => {
+                if self.state == AppState::Searching {
+                    self.cancel_search();
+                }
+            }
+            ShortcutAction::NextResult | ShortcutAction::PreviousResult => {
+                // 按列表的显示顺序移动，分组后也一样
+                let forward = action == ShortcutAction::NextResult;
+                if let Some(i) = self.result_list.neighbor(self.selection.current, forward) {
+                    return Action::Select(i);
+                }
+            }
+            ShortcutAction::OpenFile => {
+                if let Some(path) = self.selected_path() {
+                    return Action::OpenFile(path);
+                }
+            }
+            ShortcutAction::OpenFolder => {
+                if let Some(path) = self.selected_path() {
+                    return Action::OpenFolder(path);
+                }
+            }
+            ShortcutAction::Preview => {
+                if let Some(path) = self.selected_path() {
+                    return Action::OpenContext(path);
+                }
+            }
+            ShortcutAction::CopyPath => {
+                if let Some(path) = self.selected_path() {
+                    return Action::CopyPath(path);
+                }
+            }
+            // 关闭最上层（获得焦点）的浮动窗口
+            ShortcutAction::CloseWindow => {
+                let top = ctx.top_layer_id();
+                if let Some(i) = self
+                    .windows
+                    .iter()
+                    .position(|w| top == Some(egui::LayerId::new(egui::Order::Middle, w.id())))
+                {
+                    self.windows.remove(i);
+                }
+            }
+        }
+        Action::None
+    }
+
+    fn perform(&mut self, ctx: &egui::Context, action: Action) {
+        match action {
+            Action::None => {}
+            Action::OpenFile(path) => {
+                if let Err(e) = opener::open(&path) {
+                    eprintln!("Failed to open file: {}", e);
+                }
+            }
+            Action::OpenFolder(path) => {
+                if let Some(parent) = std::path::Path::new(&path).parent() {
+                    if let Err(e) = opener::open(parent) {
+                        eprintln!("Failed to open folder: {}", e);
+                    }
+                }
+            }
+            Action::OpenContext(path) => {
+                if self
+                    .windows
+                    .iter()
+                    .any(|w| matches!(w, AppWindow::Context(v) if v.path == path))
+                {
+                    println!("Window for {} is already open.", &path);
+                } else if let Some(view) = self.load_preview(ctx, path) {
+                    self.windows.push(AppWindow::Context(view));
+                }
+            }
+            Action::CopyPath(path) => {
+                ctx.output_mut(|o| o.copied_text = path);
+            }
+            Action::Select(i) => self.select_result(i),
+            Action::Toggle(i) => self.selection.toggle(i),
+            Action::Extend(i) => {
+                let range = self.result_list.range(self.selection.current, i);
+                self.selection.marked.extend(range);
+                self.selection.current = Some(i);
+            }
+            Action::Export(format) => self.export_results(ctx, format),
+        }
+    }
 }
 
 enum Action {
     None,
     OpenContext(String),
     OpenFile(String),
+    OpenFolder(String),
+    CopyPath(String),
+    Select(usize),
+    // 多选：加入或移出单个结果 / 加入一段结果
+    Toggle(usize),
+    Extend(usize),
+    Export(ExportFormat),
 }
 
 impl eframe::App for MyApp {
@@ -106,30 +702,87 @@ impl eframe::App for MyApp {
         self.windows.retain(|w| *w.is_open());
 
         // 每次窗口更新，都会首先检查是否收到新消息
-        if let Ok(msg) = self.receiver.try_recv() {
+        while let Ok(msg) = self.receiver.try_recv() {
             // 信息类型的模式匹配
             match msg {
                 AppMessage::Index(index_msg) => match index_msg {
+                    // 只有界面发起的重建会发送进度，API 发起的重建不改变界面状态
                     IndexMessage::Progress(p) => {
-                        self.state = AppState::Indexing { progress: p };
+                        if matches!(self.state, AppState::Indexing { .. }) {
+                            self.state = AppState::Indexing { progress: p };
+                        }
                     }
                     // 索引完成
                     // APP状态切回空闲
                     IndexMessage::Finished => {
-                        self.state = AppState::Idle;
+                        if matches!(self.state, AppState::Indexing { .. }) {
+                            self.state = AppState::Idle;
+                        }
+                        self.reindex_needed = false;
                     }
                     IndexMessage::Error(e) => {
                         eprintln!("Indexing Error: {}", e);
-                        self.state = AppState::Idle;
+                        if matches!(self.state, AppState::Indexing { .. }) {
+                            self.state = AppState::Idle;
+                        }
                     }
                 },
-                AppMessage::Search(search_msg) => {
-                    self.cancellation_token = None; // Clear token on any search result
-                    match search_msg {
+                AppMessage::Search {
+                    generation,
+                    message,
+                } => {
+                    // 已被新查询取代的搜索
+                    if generation != self.search_generation {
+                        continue;
+                    }
+                    // Clear token once the search is over; streamed batches keep it alive
+                    if !matches!(
+                        message,
+                        SearchMessage::Hits(_) | SearchMessage::Snippets(_) | SearchMessage::Facets(_)
+                    ) {
+                        self.cancellation_token = None;
+                    }
+                    match message {
+                        // 流式结果：先显示命中，摘要随后补上
+                        SearchMessage::Hits(results) => {
+                            self.selection.remap(&self.search_results, &results);
+                            self.search_results = results;
+                            self.result_list.invalidate();
+                        }
+                        SearchMessage::Snippets(snippets) => {
+                            for (i, snippets) in snippets {
+                                if let Some(result) = self.search_results.get_mut(i) {
+                                    result.snippets = snippets;
+                                }
+                            }
+                        }
+                        SearchMessage::Facets(facets) => {
+                            self.facets = facets;
+                        }
                         // 搜索完成
-                        SearchMessage::Finished { results, duration } => {
+                        SearchMessage::Finished {
+                            results,
+                            duration,
+                            ignored,
+                        } => {
+                            if std::mem::take(&mut self.record_history) {
+                                self.history.record(&self.searching_query, results.len());
+                            }
+                            self.selection.remap(&self.search_results, &results);
                             self.search_results = results;
+                            self.result_list.invalidate();
                             self.search_duration = Some(duration);
+                            if !ignored.is_empty() {
+                                self.query_feedback = Some(QueryFeedback::ignored(
+                                    self.searching_query.clone(),
+                                    ignored,
+                                ));
+                            }
+                            self.state = AppState::Idle;
+                        }
+                        // 语法错误显示在搜索框下方，保留上一次的结果
+                        SearchMessage::QueryError { query, issues } => {
+                            self.query_feedback = Some(QueryFeedback::error(query, issues));
                             self.state = AppState::Idle;
                         }
                         SearchMessage::Cancelled => {
@@ -137,20 +790,79 @@ impl eframe::App for MyApp {
                         }
                         SearchMessage::Error(e) => {
                             eprintln!("Search Error: {}", e);
-                            self.search_results = vec![SearchResult {
-                                path: e,
-                                snippet_html: "".to_string(),
-                            }];
+                            self.search_error = Some(e);
                             self.state = AppState::Idle;
                         }
                     }
                 }
-                AppMessage::Settings(settings_msg) => match settings_msg {
-                    // 主题变更
-                    SettingsMessage::ThemeChanged(theme) => {
-                        self.settings.theme = theme;
+                AppMessage::FileOp(file_op_msg) => {
+                    let Some(status) = &mut self.file_op else {
+                        continue;
+                    };
+                    match file_op_msg {
+                        FileOpMessage::Progress { done, total } => {
+                            status.done = done;
+                            status.total = total;
+                        }
+                        FileOpMessage::Failed { path, error } => {
+                            status.errors.push(format!("{}: {}", path, error));
+                        }
+                        FileOpMessage::Finished => status.finished = true,
+                        FileOpMessage::Error(e) => {
+                            eprintln!("File operation error: {}", e);
+                            status.fatal = Some(e);
+                            status.finished = true;
+                        }
                     }
-                },
+                }
+                AppMessage::QueryBuilder(QueryBuilderMessage::Apply {
+                    query,
+                    filters,
+                    run,
+                }) => {
+                    self.search_query = query;
+                    self.facet_filters = filters;
+                    if run && !matches!(self.state, AppState::Indexing { .. }) {
+                        self.submit_search();
+                    }
+                }
+                AppMessage::Export(export_msg) => {
+                    self.export_status = Some(match export_msg {
+                        ExportMessage::Finished(count) => format!("已导出 {} 条结果", count),
+                        ExportMessage::Error(e) => {
+                            eprintln!("Failed to export results: {}", e);
+                            format!("导出失败: {}", e)
+                        }
+                    });
+                }
+                AppMessage::Preview(PreviewMessage::Matches { path, matches }) => {
+                    let windows = self.windows.iter_mut().filter_map(|w| match w {
+                        AppWindow::Context(view) => Some(view),
+                        _ => None,
+                    });
+                    for view in self.preview.iter_mut().chain(windows) {
+                        if view.path == path {
+                            view.set_query_matches(matches.clone());
+                        }
+                    }
+                }
+                AppMessage::Settings(settings_msg) => {
+                    self.apply_settings_message(settings_msg);
+                    // 接口的搜索设置与界面保持一致
+                    if let Some(server) = &self.api_server {
+                        server.set_config(self.settings.api_config());
+                    }
+                }
+            }
+        }
+
+        // 输入停顿后触发即时搜索
+        if let Some(deadline) = self.search_deadline {
+            let now = Instant::now();
+            if now < deadline {
+                ctx.request_repaint_after(deadline - now);
+            } else if !matches!(self.state, AppState::Indexing { .. }) {
+                self.start_search();
             }
         }
 
@@ -174,6 +886,18 @@ impl eframe::App for MyApp {
                         .as_ref()
                         .map_or("请选择目录", |p| p.to_str().unwrap_or_default());
                     ui.label(format!("目标目录: {}", path_str));
+                    // API 正在重建时也不能开始，同一索引同时只能有一个写入者
+                    let api_indexing = self.index_service.is_indexing(&self.index_dir)
+                        && self.state == AppState::Idle;
+                    if api_indexing {
+                        ui.label("正在通过 API 重建索引...");
+                    }
+                    if self.reindex_needed {
+                        ui.label(
+                            egui::RichText::new("分词设置已更改，请重新索引")
+                                .color(egui::Color32::from_rgb(230, 140, 0)),
+                        );
+                    }
 
                     // 添加状态驱动的组件
                     // 返回值为组件状态
@@ -192,16 +916,22 @@ impl eframe::App for MyApp {
                     // awesome
                     // 先看有索引路径，再能让索引按钮能点
                     let index_button_enabled =
-                        self.index_path.is_some() && self.state == AppState::Idle;
+                        self.index_path.is_some() && self.state == AppState::Idle && !api_indexing;
                     // 索引按钮
                     if ui
                         .add_enabled(index_button_enabled, egui::Button::new("开始索引"))
                         .clicked()
                     {
+                        let Some(indexing) = self.index_service.begin_indexing(&self.index_dir)
+                        else {
+                            return;
+                        };
                         // 转换状态
                         // 方便加载索引动画
                         self.state = AppState::Indexing { progress: 0.0 };
                         let path = self.index_path.clone().unwrap();
+                        let analyzer_options = self.settings.analyzer_options();
+                        let service = self.index_service.clone();
                         let sender = self.sender.clone();
                         // 多线程处理索引
                         // 使用另一个线程进行索引来防止卡顿
@@ -210,9 +940,13 @@ impl eframe::App for MyApp {
                         // Move的存在可以让此线程单独获得所有变量的所有权，因为update后，所有变量都可能会销毁，但索引可能会继续进行
                         // 内存安全
                         thread::spawn(move || {
-                            if let Err(e) =
-                                crate::search::indexer::index_directory(&path, sender.clone())
-                            {
+                            if let Err(e) = engine::index_directory(
+                                &service,
+                                &indexing,
+                                &path,
+                                analyzer_options,
+                                sender.clone(),
+                            ) {
                                 sender
                                     .send(AppMessage::Index(IndexMessage::Error(e.to_string())))
                                     .unwrap();
@@ -225,56 +959,97 @@ impl eframe::App for MyApp {
 
                 // --- Search Section ---
                 ui.collapsing("搜索", |ui| {
-                    ui.horizontal(|ui| {
+                    let can_search = !matches!(self.state, AppState::Indexing { .. });
+                    let search_row = ui.horizontal(|ui| {
                         ui.label("关键词: ");
-                        // 因为 add_enabled 不仅可以控制单个组件，它还可以控制一组任意复杂的UI组件
-                        // 后面传入闭包包裹一段UI代码
-                        ui.add_enabled_ui(self.state == AppState::Idle, |ui| {
-                            // 可编辑文本框
-                            ui.text_edit_singleline(&mut self.search_query);
-                        });
+                        // 搜索进行中也保持可编辑，新的输入会取代正在进行的搜索
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.search_query)
+                                .id(egui::Id::new(SEARCH_INPUT_ID)),
+                        );
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            if can_search && !self.search_query.trim().is_empty() {
+                                self.submit_search();
+                            }
+                        } else if response.changed() {
+                            // 换了查询语句，之前的筛选条件不再适用
+                            self.facet_filters.clear();
+                            if self.settings.live_search {
+                                if self.search_query.trim().is_empty() {
+                                    self.cancel_search();
+                                    self.search_results.clear();
+                                    self.result_list.invalidate();
+                                    self.facets.clear();
+                                    self.search_duration = None;
+                                    self.query_feedback = None;
+                                } else {
+                                    self.search_deadline = Some(Instant::now() + SEARCH_DEBOUNCE);
+                                }
+                            }
+                        }
+                        if ui.button("🕘").on_hover_text("搜索历史和已保存的搜索").clicked() {
+                            self.history_menu.toggle();
+                        }
+                    });
+                    let can_save = !self.search_query.trim().is_empty();
+                    let history_event = self.history_menu.show(
+                        ui.ctx(),
+                        search_row.response.rect,
+                        &self.history,
+                        can_save,
+                    );
+                    if let Some(event) = history_event {
+                        self.apply_history_event(event);
+                    }
+                    if let Some(feedback) = &self.query_feedback {
+                        feedback.draw(ui);
+                    }
+                    if let Some(error) = &self.search_error {
+                        ui.label(egui::RichText::new(error).small().color(egui::Color32::RED));
+                    }
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.settings.expand_synonyms, "同义词扩展");
+                        ui.checkbox(&mut self.settings.lenient_query, "宽松语法");
+                        ui.checkbox(&mut self.settings.live_search, "即时搜索");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("最多结果:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.result_limit)
+                                .clamp_range(10..=10000)
+                                .speed(10.0),
+                        );
                     });
 
                     // 搜索按钮
-                    let search_button_enabled =
-                        !self.search_query.is_empty() && self.state == AppState::Idle;
+                    let search_button_enabled = !self.search_query.trim().is_empty() && can_search;
                     if ui
                         .add_enabled(search_button_enabled, egui::Button::new("搜索"))
                         .clicked()
                     {
-                        self.state = AppState::Searching;
-                        let query = self.search_query.clone();
-                        let sender = self.sender.clone();
-                        let token = Arc::new(AtomicBool::new(false));
-                        self.cancellation_token = Some(token.clone());
+                        self.submit_search();
+                    }
+                    if ui.button("高级搜索").clicked() {
+                        let is_already_open = self
+                            .windows
+                            .iter()
+                            .any(|w| matches!(w, AppWindow::QueryBuilder(_)));
+                        if !is_already_open {
+                            let view = QueryBuilderView::new(
+                                self.sender.clone(),
+                                &self.search_query,
+                                &self.facet_filters,
+                            );
+                            self.windows.push(AppWindow::QueryBuilder(view));
+                        }
+                    }
 
-                        // 多线程搜索
-                        thread::spawn(move || {
-                            let sender_clone = sender.clone();
-                            let result = std::panic::catch_unwind(move || {
-                                crate::search::query::search(&query, sender_clone, token)
-                            });
-
-                            match result {
-                                Ok(Ok(_)) => {
-                                    // Search completed successfully (sent its own message)
-                                }
-                                Ok(Err(e)) => {
-                                    // Search returned a normal error
-                                    sender
-                                        .send(AppMessage::Search(SearchMessage::Error(e.to_string())))
-                                        .unwrap();
-                                }
-                                Err(panic_payload) => {
-                                    // Search panicked
-                                    eprintln!("Search thread panicked: {:?}", panic_payload);
-                                    let error_msg = "A critical error occurred in the search engine, possibly due to a corrupt file.".to_string();
-                                    sender
-                                        .send(AppMessage::Search(SearchMessage::Error(error_msg)))
-                                        .unwrap();
-                                }
-                            }
-                        });
+                    // 固定的已保存搜索
+                    if self.history.saved.iter().any(|s| s.pinned) {
+                        ui.label("常用搜索:");
+                        if let Some(event) = crate::gui::history_menu::show_pinned(ui, &self.history) {
+                            self.apply_history_event(event);
+                        }
                     }
                 });
 
@@ -294,18 +1069,89 @@ impl eframe::App for MyApp {
                             ui.spinner();
                             ui.label("正在搜索...");
                             if ui.button("停止").clicked() {
-                                if let Some(token) = &self.cancellation_token {
-                                    token.store(true, Ordering::SeqCst);
-                                }
+                                self.cancel_search();
                             }
                         });
                     }
                 }
+                if let Some(server) = &self.api_server {
+                    // 设置了 token 时带在链接里，网页打开后会从地址栏去掉
+                    let mut url = format!("http://{}/", server.addr());
+                    if !self.settings.api_token.is_empty() {
+                        url.push_str("?token=");
+                        url.extend(form_urlencoded::byte_serialize(
+                            self.settings.api_token.as_bytes(),
+                        ));
+                    }
+                    ui.hyperlink_to(
+                        egui::RichText::new(format!("网页版: http://{}/", server.addr())).small(),
+                        url,
+                    );
+                } else if let Some(e) = &self.api_error {
+                    ui.label(
+                        egui::RichText::new(format!("本地 API 启动失败: {}", e))
+                            .small()
+                            .color(egui::Color32::RED),
+                    );
+                }
             });
 
+        // Facet sidebar, only once a search has produced counts
+        if !self.facets.is_empty() || !self.facet_filters.is_empty() {
+            let event = egui::SidePanel::left("facet_panel")
+                .resizable(true)
+                .default_width(180.0)
+                .show(ctx, |ui| {
+                    crate::gui::facet_panel::show(ui, &self.facets, &self.facet_filters)
+                })
+                .inner;
+            if let Some(event) = event {
+                self.apply_facet_event(event);
+            }
+        }
+
+        let shortcut_action = self.handle_shortcuts(ctx);
+        self.perform(ctx, shortcut_action);
+
+        // Right Panel for the docked preview
+        let mut open_floating = None;
+        if self.settings.docked_preview {
+            self.sync_preview(ctx);
+            // 有浮动预览窗口时，快捷键交给最上层的窗口
+            let keyboard_active = !self
+                .windows
+                .iter()
+                .any(|w| matches!(w, AppWindow::Context(_)));
+            egui::SidePanel::right("preview_panel")
+                .resizable(true)
+                .default_width(420.0)
+                .show(ctx, |ui| match &mut self.preview {
+                    Some(view) => {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(&view.path).strong());
+                            if ui.small_button("在新窗口打开").clicked() {
+                                open_floating = Some(view.path.clone());
+                            }
+                        });
+                        ui.separator();
+                        view.show_contents(ui, keyboard_active);
+                    }
+                    None => {
+                        ui.centered_and_justified(|ui| {
+                            ui.label(
+                                egui::RichText::new("选择一条结果以预览").color(egui::Color32::GRAY),
+                            );
+                        });
+                    }
+                });
+        }
+
         // Central Panel for Results
         egui::CentralPanel::default().show(ctx, |ui| {
-            let mut action = Action::None;
+            let mut action = match open_floating {
+                Some(path) => Action::OpenContext(path),
+                None => Action::None,
+            };
 
             // 结果部分
             ui.horizontal(|ui| {
@@ -315,100 +1161,84 @@ impl eframe::App for MyApp {
                         egui::RichText::new(format!("({:.2?})", duration)).color(egui::Color32::GRAY),
                     );
                 }
-            });
-            ui.separator();
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                if self.search_results.is_empty() {
-                    ui.label("无结果");
-                }
-
-                for result in &self.search_results {
-                    let item_response = egui::Frame::group(ui.style())
-                        .show(ui, |ui| {
-                            // 文本按钮
-                            // let button =
-                            //     egui::Button::new(egui::RichText::new(&result.path).strong())
-                            //         .frame(false);
-                            // if ui.add(button).double_clicked() {
-                            //     action = Action::OpenFile(result.path.clone());
-                            // }
-                            let text = egui::RichText::new(&result.path).strong();
-                            ui.label(text);
-                            // ui.label(egui::RichText::new(&result.snippet_html).small());
-                            ui.horizontal_wrapped(|ui| {
-                                ui.spacing_mut().item_spacing.x = 0.0;
-                                ui.style_mut().override_text_style = Some(egui::TextStyle::Small);
-                                let parts = result.snippet_html.split("<b>");
-                                let mut first = true;
-                                for part in parts {
-                                    if first && part.is_empty() {
-                                        first = false;
-                                        continue;
-                                    }
-                                    if let Some(highlighted_part) = part.find("</b>") {
-                                        let (highlight, rest) = part.split_at(highlighted_part);
-                                        ui.label(
-                                            egui::RichText::new(highlight)
-                                                .color(egui::Color32::RED),
-                                        );
-                                        ui.label(&rest[4..]); // a little ugly
-                                    } else {
-                                        ui.label(part);
-                                    }
-                                    first = false;
-                                }
-                            });
-                        })
-                        .response;
-
-                    item_response.context_menu(|ui| {
-                        if ui.button("打开文件").clicked() {
-                            action = Action::OpenFile(result.path.clone());
-                            ui.close_menu();
-                        }
-                        if ui.button("预览关键词上下文").clicked() {
-                            action = Action::OpenContext(result.path.clone());
-                            ui.close_menu();
-                        }
-                        if ui.button("打开文件所在文件夹").clicked() {
-                            if let Some(parent) = std::path::Path::new(&result.path).parent() {
-                                if let Err(e) = opener::open(parent) {
-                                    eprintln!("Failed to open folder: {}", e);
+                if let Some(status) = &self.export_status {
+                    ui.label(egui::RichText::new(status).small().color(egui::Color32::GRAY));
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    // 摘要生成完之后才能导出
+                    let can_export =
+                        !self.search_results.is_empty() && self.state != AppState::Searching;
+                    ui.add_enabled_ui(can_export, |ui| {
+                        ui.menu_button("导出", |ui| {
+                            for format in ExportFormat::ALL {
+                                if ui.button(format.label()).clicked() {
+                                    action = Action::Export(format);
+                                    ui.close_menu();
                                 }
                             }
-                            ui.close_menu();
-                        }
+                        });
                     });
-
-                    ui.separator();
-                }
+                    ui.checkbox(&mut self.settings.docked_preview, "预览面板");
+                    egui::ComboBox::from_id_source("sort_by")
+                        .selected_text(self.settings.sort_by.label())
+                        .show_ui(ui, |ui| {
+                            for sort_by in SortBy::ALL {
+                                ui.selectable_value(
+                                    &mut self.settings.sort_by,
+                                    sort_by,
+                                    sort_by.label(),
+                                );
+                            }
+                        });
+                    ui.label("排序:");
+                    egui::ComboBox::from_id_source("group_by")
+                        .selected_text(self.settings.group_by.label())
+                        .show_ui(ui, |ui| {
+                            for group_by in GroupBy::ALL {
+                                ui.selectable_value(
+                                    &mut self.settings.group_by,
+                                    group_by,
+                                    group_by.label(),
+                                );
+                            }
+                        });
+                    ui.label("分组:");
+                });
             });
-
-            // Perform the action after the loop
-            if let Action::OpenFile(path) = action {
-                if let Err(e) = opener::open(&path) {
-                    eprintln!("Failed to open file: {}", e);
-                }
-            } else if let Action::OpenContext(path) = action {
-                if self
-                    .windows
-                    .iter()
-                    .any(|w| matches!(w, AppWindow::Context(v) if v.path == path))
-                {
-                    println!("Window for {} is already open.", &path);
-                } else {
-                    match crate::utils::file_utils::read_file_content(std::path::Path::new(&path)) {
-                        Ok(content) => {
-                            self.windows.push(AppWindow::Context(
-                                crate::gui::context_view::ContextView::new(path, content),
-                            ));
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to read file for context view: {}", e);
-                        }
-                    }
+            ui.separator();
+            if self.search_results.is_empty() {
+                ui.label("无结果");
+            } else {
+                let marked = self.selection.marked.len();
+                if let Some(event) = crate::gui::batch_bar::show(ui, marked, self.file_op.as_ref()) {
+                    self.apply_batch_event(event);
                 }
             }
+            let event = self.result_list.show(
+                ui,
+                &self.search_results,
+                self.settings.group_by,
+                self.settings.sort_by,
+                &self.selection,
+                std::mem::take(&mut self.scroll_to_selected),
+                self.state == AppState::Searching,
+                self.settings.snippets_per_result,
+            );
+            if let Some(event) = event {
+                let path = |i: usize| self.search_results[i].path.clone();
+                action = match event {
+                    ResultEvent::Select(i) => Action::Select(i),
+                    ResultEvent::Toggle(i) => Action::Toggle(i),
+                    ResultEvent::Extend(i) => Action::Extend(i),
+                    ResultEvent::OpenFile(i) => Action::OpenFile(path(i)),
+                    ResultEvent::OpenFolder(i) => Action::OpenFolder(path(i)),
+                    ResultEvent::OpenContext(i) => Action::OpenContext(path(i)),
+                    ResultEvent::CopyPath(i) => Action::CopyPath(path(i)),
+                };
+            }
+
+            // Perform the action after drawing the list
+            self.perform(ctx, action);
         });
 
         // Floating Settings Button
@@ -426,7 +1256,7 @@ impl eframe::App for MyApp {
                         self.windows.iter().any(|w| matches!(w, AppWindow::Settings(_)));
 
                     if !is_already_open {
-                        let view = SettingsView::new(self.sender.clone(), self.settings.theme);
+                        let view = SettingsView::new(self.sender.clone(), &self.settings);
                         self.windows.push(AppWindow::Settings(view));
                     }
                 }
@@ -448,7 +1278,8 @@ impl eframe::App for MyApp {
                 );
             });
 
-        if self.state != AppState::Idle || !self.windows.is_empty() {
+        let file_op_running = self.file_op.as_ref().is_some_and(|s| !s.finished);
+        if self.state != AppState::Idle || !self.windows.is_empty() || file_op_running {
             ctx.request_repaint();
         }
     }
@@ -459,6 +1290,13 @@ impl eframe::App for MyApp {
 }
 
 fn main() -> Result<(), eframe::Error> {
+    // 带子命令时作为命令行工具运行，不打开窗口
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::is_command(&args) {
+        attach_console();
+        std::process::exit(cli::run(&args));
+    }
+
     // 窗口基础配置
     let options = eframe::NativeOptions {
         // 窗口大小控制
@@ -468,7 +1306,7 @@ fn main() -> Result<(), eframe::Error> {
     };
     // eframe窗口创建
     eframe::run_native(
-        "MyTxt",
+        config::APP_ID,
         options,
         // 用于初始状态的闭包
         Box::new(|cc| {
@@ -498,3 +1336,21 @@ fn main() -> Result<(), eframe::Error> {
         }),
     )
 }
+
+// 程序按 Windows 图形子系统编译，没有自己的控制台；
+// 从终端运行命令时接到父进程的控制台上，命令行输出才看得见
+#[cfg(windows)]
+fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // 没有父控制台（如从资源管理器启动）时调用失败，输出照旧被丢弃
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
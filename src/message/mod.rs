@@ -1,5 +1,10 @@
-use crate::config::Theme;
+use crate::config::{AnalyzerKind, ShortcutAction, Theme};
+use crate::search::diagnostics::QueryIssue;
+use crate::search::facets::{FacetFilter, FacetSummary};
+use crate::search::highlight::HighlightedText;
 use crate::search::query::SearchResult;
+use eframe::egui::KeyboardShortcut;
+use std::ops::Range;
 use std::time::Duration;
 
 // --- Module-specific messages ---
@@ -13,23 +18,105 @@ pub enum IndexMessage {
 
 #[derive(Debug)]
 pub enum SearchMessage {
+    // 目前为止的排名（尚无摘要），整体替换界面上的结果列表
+    Hits(Vec<SearchResult>),
+    // 为最近一次 Hits 中对应下标的结果补上摘要
+    Snippets(Vec<(usize, Vec<HighlightedText>)>),
+    // 全部命中（不受结果数上限影响）在各维度上的分布
+    Facets(Vec<FacetSummary>),
     Finished {
         results: Vec<SearchResult>,
         duration: Duration,
+        // 宽松模式下被忽略的部分
+        ignored: Vec<QueryIssue>,
+    },
+    // 查询语句无法解析，搜索未执行
+    QueryError {
+        query: String,
+        issues: Vec<QueryIssue>,
     },
     Cancelled,
     Error(String),
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum SettingsMessage {
     ThemeChanged(Theme),
+    AnalyzerChanged(AnalyzerKind),
+    DictionaryChanged { user_dict: String, stopwords: String },
+    SynonymsChanged(String),
+    SnippetsChanged { length: usize, count: usize },
+    ShortcutChanged(ShortcutAction, KeyboardShortcut),
+    ShortcutsReset,
+    // 本地 HTTP 接口的开关、端口和 token，应用后重启接口
+    ApiChanged {
+        enabled: bool,
+        port: u16,
+        token: String,
+    },
+}
+
+// 复制、移动、打包结果文件的进度
+#[derive(Debug)]
+pub enum FileOpMessage {
+    Progress { done: usize, total: usize },
+    // 单个文件失败，其余文件继续处理
+    Failed { path: String, error: String },
+    Finished,
+    // 整个操作无法继续，如无法创建压缩包
+    Error(String),
+}
+
+#[derive(Debug)]
+pub enum QueryBuilderMessage {
+    // 将生成的查询语句和筛选条件填入搜索框，run 为 true 时立即搜索
+    Apply {
+        query: String,
+        filters: Vec<FacetFilter>,
+        run: bool,
+    },
+}
+
+// 导出搜索结果的结果
+#[derive(Debug)]
+pub enum ExportMessage {
+    // 已写入的条数
+    Finished(usize),
+    Error(String),
+}
+
+// 预览窗口在后台算出的内容
+#[derive(Debug)]
+pub enum PreviewMessage {
+    // 主查询在 path 对应文件中的命中位置（字节范围）
+    Matches {
+        path: String,
+        matches: Vec<Range<usize>>,
+    },
 }
 
 // --- Top-level message router ---
 #[derive(Debug)]
 pub enum AppMessage {
     Index(IndexMessage),
-    Search(SearchMessage),
+    // generation 标识发起搜索的那一次查询，过期的结果会被丢弃
+    Search {
+        generation: u64,
+        message: SearchMessage,
+    },
     Settings(SettingsMessage),
-}
\ No newline at end of file
+    QueryBuilder(QueryBuilderMessage),
+    FileOp(FileOpMessage),
+    Export(ExportMessage),
+    Preview(PreviewMessage),
+}
+
+impl AppMessage {
+    pub fn search(generation: u64, message: SearchMessage) -> Self {
+        AppMessage::Search {
+            generation,
+            message,
+        }
+    }
+}
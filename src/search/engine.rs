@@ -1,7 +1,118 @@
-use once_cell::sync::Lazy;
-use std::sync::RwLock;
-use tantivy::{Index, IndexReader};
+use crate::config::APP_ID;
+use crate::message::{AppMessage, IndexMessage, SearchMessage};
+use crate::search::analyzer::AnalyzerOptions;
+use crate::search::facets::FacetSummary;
+use crate::search::highlight::HighlightedText;
+use crate::search::query::{SearchError, SearchRequest, SearchResult};
+use anyhow::Result;
+use crossbeam_channel::Sender;
+use mytxt_core::{IndexObserver, IndexService, IndexingGuard, SearchObserver};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
-// Use Lazy to initialize the RwLock wrapping our optional Index and Reader.
-// This will be our globally accessible, thread-safe index holder.
-pub static INDEX: Lazy<RwLock<Option<(Index, IndexReader)>>> = Lazy::new(|| RwLock::new(None));
+const INDEX_DIR: &str = "tantivy_index";
+
+// 界面、命令行和 API 共用的索引目录：放在应用数据目录下，与启动时的工作目录无关；
+// 找不到数据目录时退回到工作目录下
+pub fn index_dir() -> PathBuf {
+    eframe::storage_dir(APP_ID).map_or_else(|| PathBuf::from(INDEX_DIR), |dir| dir.join(INDEX_DIR))
+}
+
+// 在后台线程中重建索引，完成后替换当前索引；进度以 IndexMessage 发给界面
+pub fn index_directory(
+    service: &IndexService,
+    guard: &IndexingGuard,
+    path: &Path,
+    analyzer_options: AnalyzerOptions,
+    sender: Sender<AppMessage>,
+) -> Result<()> {
+    service.index_directory(
+        guard,
+        path,
+        &analyzer_options,
+        &IndexProgress(sender.clone()),
+    )?;
+    sender.send(AppMessage::Index(IndexMessage::Finished))?;
+    Ok(())
+}
+
+// 在后台线程中执行搜索，中间结果和最终结果都以 SearchMessage 发给界面
+pub fn search(
+    service: &IndexService,
+    index_dir: &Path,
+    analyzer_options: &AnalyzerOptions,
+    request: SearchRequest,
+    generation: u64,
+    sender: Sender<AppMessage>,
+    cancel_token: Arc<AtomicBool>,
+) -> Result<()> {
+    // 本次启动还没有建立索引时，打开上次建立的索引
+    let searcher = service.open(index_dir, analyzer_options)?;
+    let observer = SearchProgress {
+        sender: sender.clone(),
+        generation,
+    };
+    let message = match searcher.search(&request, &observer, cancel_token) {
+        Ok(response) => SearchMessage::Finished {
+            results: response.results,
+            duration: response.duration,
+            ignored: response.ignored,
+        },
+        Err(SearchError::Query(issues)) => SearchMessage::QueryError {
+            query: request.query,
+            issues,
+        },
+        Err(SearchError::Cancelled) => SearchMessage::Cancelled,
+        Err(SearchError::Failed(e)) => return Err(e),
+    };
+    sender.send(AppMessage::search(generation, message))?;
+    Ok(())
+}
+
+// 界面已关闭时发送会失败，此时没有人需要进度，直接忽略
+struct IndexProgress(Sender<AppMessage>);
+
+impl IndexObserver for IndexProgress {
+    fn progress(&self, done: usize, total: usize) {
+        let progress = if total > 0 {
+            done as f32 / total as f32
+        } else {
+            1.0 // Avoid division by zero
+        };
+        let _ = self
+            .0
+            .send(AppMessage::Index(IndexMessage::Progress(progress)));
+    }
+
+    fn file_failed(&self, path: &Path, error: &anyhow::Error) {
+        eprintln!("Failed to process file {:?}: {}", path, error);
+    }
+}
+
+struct SearchProgress {
+    sender: Sender<AppMessage>,
+    generation: u64,
+}
+
+impl SearchProgress {
+    fn send(&self, message: SearchMessage) {
+        let _ = self
+            .sender
+            .send(AppMessage::search(self.generation, message));
+    }
+}
+
+impl SearchObserver for SearchProgress {
+    fn hits(&self, hits: &[SearchResult]) {
+        self.send(SearchMessage::Hits(hits.to_vec()));
+    }
+
+    fn snippets(&self, snippets: &[(usize, Vec<HighlightedText>)]) {
+        self.send(SearchMessage::Snippets(snippets.to_vec()));
+    }
+
+    fn facets(&self, facets: &[FacetSummary]) {
+        self.send(SearchMessage::Facets(facets.to_vec()));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::config::{GroupBy, SortBy};
+use crate::search::query::SearchResult;
+use std::collections::HashMap;
+use std::path::Path;
+
+const UNKNOWN_MONTH: &str = "未知时间";
+
+pub struct ResultGroup {
+    pub key: String,
+    // 组内结果在结果列表中的下标，按显示顺序排列
+    pub results: Vec<usize>,
+}
+
+pub fn group_key(result: &SearchResult, group_by: GroupBy) -> String {
+    match group_by {
+        GroupBy::None => String::new(),
+        GroupBy::Folder => Path::new(&result.path)
+            .parent()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default(),
+        GroupBy::Root if result.root.is_empty() => "未知目录".to_string(),
+        GroupBy::Root => result.root.clone(),
+        GroupBy::Extension if result.extension.is_empty() => "无扩展名".to_string(),
+        GroupBy::Extension => result.extension.clone(),
+        GroupBy::Month => match result.modified {
+            Some(modified) => {
+                let date = modified.into_utc();
+                format!("{}-{:02}", date.year(), date.month() as u8)
+            }
+            None => UNKNOWN_MONTH.to_string(),
+        },
+    }
+}
+
+// 结果下标按显示顺序排列；没有修改时间的结果排在最后
+pub fn sort_order(results: &[SearchResult], sort_by: SortBy) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..results.len()).collect();
+    match sort_by {
+        SortBy::Relevance => {}
+        SortBy::Newest => order.sort_by_key(|&i| std::cmp::Reverse(results[i].modified)),
+        SortBy::Oldest => order.sort_by_key(|&i| {
+            let modified = results[i].modified;
+            (modified.is_none(), modified)
+        }),
+        SortBy::Path => order.sort_by(|&a, &b| results[a].path.cmp(&results[b].path)),
+    }
+    order
+}
+
+// 组按 order 中首次出现的先后排列；按月份分组时按时间倒序
+pub fn group_results(
+    results: &[SearchResult],
+    order: &[usize],
+    group_by: GroupBy,
+) -> Vec<ResultGroup> {
+    let mut groups: Vec<ResultGroup> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for &i in order {
+        let result = &results[i];
+        let key = group_key(result, group_by);
+        let position = *positions.entry(key.clone()).or_insert_with(|| {
+            groups.push(ResultGroup {
+                key,
+                results: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[position].results.push(i);
+    }
+    if group_by == GroupBy::Month {
+        groups.sort_by(|a, b| {
+            (a.key == UNKNOWN_MONTH)
+                .cmp(&(b.key == UNKNOWN_MONTH))
+                .then_with(|| b.key.cmp(&a.key))
+        });
+    }
+    groups
+}
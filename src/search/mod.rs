@@ -1,3 +1,5 @@
+pub use mytxt_core::{analyzer, diagnostics, facets, highlight, query, synonyms};
+
 pub mod engine;
-pub mod indexer;
-pub mod query;
+pub mod grouping;
+pub mod query_builder;
@@ -0,0 +1,311 @@
+use crate::search::facets::{self, FacetField, FacetFilter};
+use tantivy::query_grammar::{self, Delimiter, Occur, UserInputAst, UserInputLeaf};
+
+// 高级搜索中的一行条件
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryClause {
+    pub occur: Occur,
+    pub field: QueryField,
+    pub text: String,
+    // true: 作为短语匹配，slop 为允许的词间距
+    pub phrase: bool,
+    pub slop: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryField {
+    Content,
+    Path,
+}
+
+impl QueryField {
+    pub const ALL: [QueryField; 2] = [QueryField::Content, QueryField::Path];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueryField::Content => "content",
+            QueryField::Path => "path",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QueryField::Content => "正文",
+            QueryField::Path => "路径",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+}
+
+impl Default for QueryClause {
+    fn default() -> Self {
+        Self {
+            occur: Occur::Must,
+            field: QueryField::Content,
+            text: String::new(),
+            phrase: false,
+            slop: 0,
+        }
+    }
+}
+
+// 高级搜索中的筛选条件，每个维度一行；值为 "项目/2024" 这样的路径，留空表示不筛选
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterRow {
+    pub field: FacetField,
+    pub value: String,
+}
+
+impl FilterRow {
+    pub fn hint(&self) -> &'static str {
+        match self.field {
+            FacetField::Folder => "如 项目/2024",
+            FacetField::FileType => "如 docx",
+            FacetField::Year => "如 2024",
+        }
+    }
+}
+
+// 用当前的筛选条件填充各维度的行，与侧栏一样每个维度只有一个条件
+pub fn filter_rows(filters: &[FacetFilter]) -> Vec<FilterRow> {
+    FacetField::ALL
+        .into_iter()
+        .map(|field| FilterRow {
+            field,
+            value: filters
+                .iter()
+                .rev()
+                .find(|f| f.field == field)
+                .map(|f| facets::facet_path(&f.facet))
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+pub fn to_filters(rows: &[FilterRow]) -> Vec<FacetFilter> {
+    rows.iter()
+        .filter_map(|row| FacetFilter::parse(row.field.field_name(), row.value.trim()))
+        .collect()
+}
+
+// Characters the query grammar only accepts escaped inside a bare word.
+const ESCAPE_IN_WORD: &[char] = &['^', '`', ':', '{', '}', '"', '\'', '[', ']', '(', ')', '\\'];
+
+// 运算符关键字和以 + 开头的词只能作为短语出现：
+// 词首的 + 会被当作“必须”，而语法在词中不认 \+ 转义，会把反斜杠留在词里
+fn quoted_word(word: &str) -> bool {
+    matches!(word, "AND" | "OR" | "NOT" | "IN") || word.starts_with('+')
+}
+
+fn escape_word(word: &str) -> String {
+    if quoted_word(word) {
+        return format!("\"{}\"", escape_phrase(word));
+    }
+    let mut escaped = String::with_capacity(word.len());
+    for (i, c) in word.chars().enumerate() {
+        // 词首的 - 会被当作排除
+        if ESCAPE_IN_WORD.contains(&c) || (i == 0 && c == '-') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_phrase(phrase: &str) -> String {
+    phrase.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Render the clauses as a query string the main search box understands.
+// A non-phrase row with several words becomes one clause per word.
+pub fn to_query_string(clauses: &[QueryClause]) -> String {
+    let mut parts = Vec::new();
+    for clause in clauses {
+        let text = clause.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let prefix = match clause.occur {
+            Occur::Must => "+",
+            Occur::MustNot => "-",
+            Occur::Should => "",
+        };
+        let field = match clause.field {
+            QueryField::Content => String::new(),
+            field => format!("{}:", field.name()),
+        };
+        if clause.phrase {
+            let slop = if clause.slop > 0 {
+                format!("~{}", clause.slop)
+            } else {
+                String::new()
+            };
+            parts.push(format!("{prefix}{field}\"{}\"{slop}", escape_phrase(text)));
+        } else {
+            for word in text.split_whitespace() {
+                parts.push(format!("{prefix}{field}{}", escape_word(word)));
+            }
+        }
+    }
+    parts.join(" ")
+}
+
+// Turn a typed query back into clauses. Only flat queries made of terms and
+// phrases can be edited visually; anything else is reported as an error.
+pub fn from_query_string(query: &str) -> Result<Vec<QueryClause>, String> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let ast = query_grammar::parse_query(query).map_err(|_| "查询语句存在语法错误".to_string())?;
+    match ast {
+        UserInputAst::Clause(sub_queries) => sub_queries
+            .into_iter()
+            .map(|(occur, ast)| to_clause(occur.unwrap_or(Occur::Should), ast))
+            .collect(),
+        leaf => Ok(vec![to_clause(Occur::Should, leaf)?]),
+    }
+}
+
+fn to_clause(occur: Occur, ast: UserInputAst) -> Result<QueryClause, String> {
+    let UserInputAst::Leaf(leaf) = ast else {
+        return Err("包含嵌套括号或权重，无法可视化编辑".to_string());
+    };
+    let UserInputLeaf::Literal(literal) = *leaf else {
+        return Err("包含范围或集合查询，无法可视化编辑".to_string());
+    };
+    if literal.prefix {
+        return Err("包含前缀查询，无法可视化编辑".to_string());
+    }
+    let field = match &literal.field_name {
+        None => QueryField::Content,
+        Some(name) => QueryField::from_name(name).ok_or_else(|| format!("未知字段: {}", name))?,
+    };
+    // escape_word 加上的引号还原为普通词
+    let quoted = matches!(literal.delimiter, Delimiter::DoubleQuotes)
+        && literal.slop == 0
+        && !literal.phrase.contains(char::is_whitespace)
+        && quoted_word(&literal.phrase);
+    Ok(QueryClause {
+        occur,
+        field,
+        phrase: !quoted && !matches!(literal.delimiter, Delimiter::None),
+        text: literal.phrase,
+        slop: literal.slop,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(occur: Occur, field: QueryField, text: &str) -> QueryClause {
+        QueryClause {
+            occur,
+            field,
+            text: text.to_string(),
+            ..QueryClause::default()
+        }
+    }
+
+    fn phrase(occur: Occur, text: &str, slop: u32) -> QueryClause {
+        QueryClause {
+            occur,
+            text: text.to_string(),
+            phrase: true,
+            slop,
+            ..QueryClause::default()
+        }
+    }
+
+    fn round_trip(clauses: &[QueryClause]) {
+        let query = to_query_string(clauses);
+        assert_eq!(
+            from_query_string(&query).as_deref(),
+            Ok(clauses),
+            "{}",
+            query
+        );
+    }
+
+    #[test]
+    fn renders_occur_field_and_phrases() {
+        let clauses = [
+            word(Occur::Must, QueryField::Content, "合同"),
+            word(Occur::MustNot, QueryField::Path, "草稿"),
+            word(Occur::Should, QueryField::Content, "2024"),
+            phrase(Occur::Must, "销售 报告", 2),
+        ];
+        assert_eq!(
+            to_query_string(&clauses),
+            "+合同 -path:草稿 2024 +\"销售 报告\"~2"
+        );
+    }
+
+    #[test]
+    fn splits_words_and_skips_empty_rows() {
+        let clauses = [
+            word(Occur::Must, QueryField::Content, " 合同  付款 "),
+            word(Occur::Must, QueryField::Content, "  "),
+        ];
+        assert_eq!(to_query_string(&clauses), "+合同 +付款");
+    }
+
+    #[test]
+    fn operator_keywords_become_phrases() {
+        let clauses = [word(Occur::Should, QueryField::Content, "AND OR")];
+        assert_eq!(to_query_string(&clauses), "\"AND\" \"OR\"");
+        round_trip(&[
+            word(Occur::Must, QueryField::Content, "NOT"),
+            word(Occur::Should, QueryField::Content, "IN"),
+        ]);
+    }
+
+    #[test]
+    fn leading_plus_and_minus_keep_their_occur() {
+        let clauses = [
+            word(Occur::Should, QueryField::Content, "+1"),
+            word(Occur::Should, QueryField::Content, "-合同"),
+        ];
+        assert_eq!(to_query_string(&clauses), "\"+1\" \\-合同");
+        // 仍是 Should，不会变成必须 / 排除
+        round_trip(&clauses);
+    }
+
+    #[test]
+    fn special_characters_round_trip() {
+        round_trip(&[
+            word(Occur::Must, QueryField::Content, "a:b"),
+            word(Occur::Should, QueryField::Path, "(草稿)"),
+            word(Occur::MustNot, QueryField::Content, "c\\d"),
+        ]);
+        round_trip(&[phrase(Occur::Should, "他说\"好\" \\ 了", 0)]);
+    }
+
+    #[test]
+    fn parsed_queries_round_trip() {
+        for query in [
+            "+合同 -path:草稿 2024",
+            "\"销售 报告\"~2 path:报告",
+            "a\\:b",
+        ] {
+            let clauses = from_query_string(query).unwrap();
+            round_trip(&clauses);
+            assert_eq!(
+                from_query_string(&to_query_string(&clauses)),
+                from_query_string(query)
+            );
+        }
+    }
+
+    #[test]
+    fn nested_queries_cannot_be_edited() {
+        assert!(from_query_string("(a OR b) AND c").is_err());
+        assert!(from_query_string("content:[a TO b]").is_err());
+        assert!(from_query_string("合同^2").is_err());
+        assert!(from_query_string("unknown:合同").is_err());
+        assert_eq!(from_query_string("  "), Ok(Vec::new()));
+    }
+}
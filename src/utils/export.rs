@@ -0,0 +1,242 @@
+use crate::search::highlight::HighlightedText;
+use crate::search::query::SearchResult;
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tantivy::DateTime;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+    Html,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Csv,
+        ExportFormat::JsonLines,
+        ExportFormat::Html,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV 表格",
+            ExportFormat::JsonLines => "JSON Lines",
+            ExportFormat::Html => "HTML 报告",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+// 导出的一行；文件大小在导出时读取，文件已不存在时为 None
+#[derive(Serialize)]
+struct ExportRow<'a> {
+    path: &'a str,
+    score: f32,
+    modified: Option<String>,
+    size: Option<u64>,
+    snippets: Vec<&'a str>,
+}
+
+impl<'a> ExportRow<'a> {
+    fn new(result: &'a SearchResult) -> Self {
+        Self {
+            path: &result.path,
+            score: result.score,
+            modified: result.modified.map(format_time),
+            size: std::fs::metadata(&result.path).ok().map(|m| m.len()),
+            snippets: result.snippets.iter().map(|s| s.text.as_str()).collect(),
+        }
+    }
+}
+
+// 把全部结果（包括未显示、已折叠的）按相关度顺序写入文件，返回写入的条数
+pub fn export(
+    path: &Path,
+    format: ExportFormat,
+    query: &str,
+    results: &[SearchResult],
+) -> Result<usize> {
+    let mut out = BufWriter::new(std::fs::File::create(path)?);
+    write_to(&mut out, format, query, results)?;
+    out.flush()?;
+    Ok(results.len())
+}
+
+// 写入任意输出，命令行的 --json 用它输出到 stdout
+pub fn write_to(
+    out: &mut impl Write,
+    format: ExportFormat,
+    query: &str,
+    results: &[SearchResult],
+) -> Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(out, results),
+        ExportFormat::JsonLines => write_json_lines(out, results),
+        ExportFormat::Html => write_html(out, query, results),
+    }
+}
+
+fn write_csv(out: &mut impl Write, results: &[SearchResult]) -> Result<()> {
+    // 带 BOM，Excel 打开时才能正确识别 UTF-8 中文
+    out.write_all("\u{feff}".as_bytes())?;
+    writeln!(out, "path,score,modified,size,snippet")?;
+    for result in results {
+        let row = ExportRow::new(result);
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            csv_field(row.path),
+            row.score,
+            row.modified.unwrap_or_default(),
+            row.size.map(|s| s.to_string()).unwrap_or_default(),
+            csv_field(&row.snippets.join(" … ")),
+        )?;
+    }
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    // 以这些字符开头的单元格会被表格软件当作公式执行，前面加 ' 使其按文本显示
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn write_json_lines(out: &mut impl Write, results: &[SearchResult]) -> Result<()> {
+    for result in results {
+        serde_json::to_writer(&mut *out, &ExportRow::new(result))?;
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+// 单个文件的报告，样式内联，不依赖外部资源
+fn write_html(out: &mut impl Write, query: &str, results: &[SearchResult]) -> Result<()> {
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<title>搜索结果：{}</title>",
+        escape_html(query)
+    )?;
+    html.push_str(
+        "<style>\n\
+         body { font-family: \"Microsoft YaHei\", sans-serif; margin: 2em; color: #222; }\n\
+         table { border-collapse: collapse; width: 100%; }\n\
+         th, td { border: 1px solid #ccc; padding: 6px 8px; text-align: left; vertical-align: top; }\n\
+         th { background: #f0f0f0; }\n\
+         td.path { word-break: break-all; }\n\
+         td.number { text-align: right; white-space: nowrap; }\n\
+         p.snippet { margin: 0 0 4px 0; }\n\
+         mark { background: #ffe066; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    writeln!(html, "<h1>搜索结果：{}</h1>", escape_html(query))?;
+    writeln!(
+        html,
+        "<p>共 {} 条，导出于 {}</p>",
+        results.len(),
+        format_time(now())
+    )?;
+    html.push_str(
+        "<table>\n<tr><th>#</th><th>路径</th><th>相关度</th><th>修改时间</th><th>大小</th><th>摘要</th></tr>\n",
+    );
+    for (i, result) in results.iter().enumerate() {
+        let row = ExportRow::new(result);
+        write!(
+            html,
+            "<tr><td class=\"number\">{}</td><td class=\"path\">{}</td><td class=\"number\">{:.2}</td><td>{}</td><td class=\"number\">{}</td><td>",
+            i + 1,
+            escape_html(row.path),
+            row.score,
+            row.modified.unwrap_or_default(),
+            row.size.map(format_size).unwrap_or_default(),
+        )?;
+        for snippet in &result.snippets {
+            writeln!(
+                html,
+                "<p class=\"snippet\">{}</p>",
+                highlighted_html(snippet)
+            )?;
+        }
+        html.push_str("</td></tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    out.write_all(html.as_bytes())?;
+    Ok(())
+}
+
+fn highlighted_html(snippet: &HighlightedText) -> String {
+    let mut html = String::new();
+    let mut cursor = 0;
+    for range in &snippet.highlights {
+        html.push_str(&escape_html(&snippet.text[cursor..range.start]));
+        html.push_str("<mark>");
+        html.push_str(&escape_html(&snippet.text[range.clone()]));
+        html.push_str("</mark>");
+        cursor = range.end;
+    }
+    html.push_str(&escape_html(&snippet.text[cursor..]));
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// UTC 时间，如 2024-05-01 08:30:00Z
+fn format_time(time: DateTime) -> String {
+    let t = time.into_utc();
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}Z",
+        t.year(),
+        t.month() as u8,
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second()
+    )
+}
+
+fn now() -> DateTime {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    DateTime::from_timestamp_secs(secs as i64)
+}
+
+fn format_size(size: u64) -> String {
+    match size {
+        0..=1023 => format!("{} B", size),
+        1024..=1_048_575 => format!("{:.1} KB", size as f64 / 1024.0),
+        _ => format!("{:.1} MB", size as f64 / 1_048_576.0),
+    }
+}
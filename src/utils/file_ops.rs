@@ -0,0 +1,143 @@
+use crate::message::{AppMessage, FileOpMessage};
+use anyhow::{bail, Result};
+use crossbeam_channel::Sender;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+// 对多选结果文件的批量操作
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileOperation {
+    Copy,
+    Move,
+    // 打包为一个 .zip 文件
+    Zip,
+}
+
+impl FileOperation {
+    pub fn label(self) -> &'static str {
+        match self {
+            FileOperation::Copy => "复制",
+            FileOperation::Move => "移动",
+            FileOperation::Zip => "打包",
+        }
+    }
+}
+
+// 一个待处理的文件，relative 为它在目标文件夹或压缩包中的路径
+pub struct FileItem {
+    pub source: PathBuf,
+    pub relative: PathBuf,
+}
+
+impl FileItem {
+    // 保留索引根目录下的相对路径，不在根目录下的文件只保留文件名
+    pub fn new(path: &str, root: &str) -> Self {
+        let source = PathBuf::from(path);
+        let relative = match source.strip_prefix(root) {
+            Ok(relative) if !root.is_empty() => relative.to_path_buf(),
+            _ => source.file_name().map(PathBuf::from).unwrap_or_default(),
+        };
+        Self { source, relative }
+    }
+}
+
+// 界面上显示的操作进度，由 FileOpMessage 更新
+pub struct FileOpStatus {
+    pub operation: FileOperation,
+    pub done: usize,
+    pub total: usize,
+    // 处理失败的单个文件
+    pub errors: Vec<String>,
+    // 整个操作失败的原因
+    pub fatal: Option<String>,
+    pub finished: bool,
+}
+
+impl FileOpStatus {
+    pub fn new(operation: FileOperation, total: usize) -> Self {
+        Self {
+            operation,
+            done: 0,
+            total,
+            errors: Vec::new(),
+            fatal: None,
+            finished: false,
+        }
+    }
+}
+
+// 在后台线程中执行；单个文件失败只报告错误，不中断其余文件
+pub fn run(
+    operation: FileOperation,
+    items: Vec<FileItem>,
+    destination: &Path,
+    sender: Sender<AppMessage>,
+) -> Result<()> {
+    let total = items.len();
+    let mut zip = match operation {
+        FileOperation::Zip => Some(ZipWriter::new(File::create(destination)?)),
+        _ => None,
+    };
+
+    for (i, item) in items.iter().enumerate() {
+        let result = match &mut zip {
+            Some(zip) => add_to_zip(zip, item),
+            None => transfer(
+                &item.source,
+                &destination.join(&item.relative),
+                operation == FileOperation::Move,
+            ),
+        };
+        if let Err(e) = result {
+            sender.send(AppMessage::FileOp(FileOpMessage::Failed {
+                path: item.source.display().to_string(),
+                error: e.to_string(),
+            }))?;
+        }
+        sender.send(AppMessage::FileOp(FileOpMessage::Progress {
+            done: i + 1,
+            total,
+        }))?;
+    }
+
+    if let Some(mut zip) = zip {
+        zip.finish()?;
+    }
+    sender.send(AppMessage::FileOp(FileOpMessage::Finished))?;
+    Ok(())
+}
+
+// 复制到 target，remove_source 为 true 时即为移动
+fn transfer(source: &Path, target: &Path, remove_source: bool) -> Result<()> {
+    // 不覆盖目标文件夹中已有的文件
+    if target.exists() {
+        bail!("目标文件已存在: {}", target.display());
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if !remove_source {
+        std::fs::copy(source, target)?;
+    } else if std::fs::rename(source, target).is_err() {
+        // 跨磁盘时 rename 会失败，改为复制后删除
+        std::fs::copy(source, target)?;
+        std::fs::remove_file(source)?;
+    }
+    Ok(())
+}
+
+fn add_to_zip(zip: &mut ZipWriter<File>, item: &FileItem) -> Result<()> {
+    // 先打开源文件，失败时不会在压缩包里留下空条目
+    let mut file = File::open(&item.source)?;
+    let name = item
+        .relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    zip.start_file(name, SimpleFileOptions::default())?;
+    std::io::copy(&mut file, zip)?;
+    Ok(())
+}
@@ -1 +1,4 @@
-pub mod file_utils;
+pub use mytxt_core::{docx_document, file_utils};
+
+pub mod export;
+pub mod file_ops;